@@ -0,0 +1,31 @@
+<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.10" tiledversion="1.10.2" name="hall" tilewidth="32" tileheight="32" tilecount="5" columns="5">
+ <image source="hall.png" width="160" height="32"/>
+ <tile id="0">
+  <properties>
+   <property name="tile_id" type="int" value="1"/>
+  </properties>
+ </tile>
+ <tile id="1">
+  <properties>
+   <property name="tile_id" type="int" value="2"/>
+  </properties>
+ </tile>
+ <tile id="2">
+  <properties>
+   <property name="tile_id" type="int" value="8"/>
+  </properties>
+ </tile>
+ <tile id="3">
+  <properties>
+   <property name="tile_id" type="int" value="20"/>
+  </properties>
+ </tile>
+ <tile id="4">
+  <properties>
+   <property name="tile_id" type="int" value="30"/>
+   <property name="color" type="color" value="#ff8a6f4e"/>
+   <property name="footstep" value="wood"/>
+  </properties>
+ </tile>
+</tileset>
@@ -0,0 +1,27 @@
+//! Build check that the crate links into a `no_std` program.
+//!
+//! Cargo builds examples with the workspace tests, and pulling `std` into the
+//! crate would clash with the panic handler below.
+#![no_std]
+
+extern crate ray_casting_fixed;
+
+use core::panic::PanicInfo;
+use ray_casting_fixed::{cast_ray, fixed, Angle, FixedBoard};
+
+#[panic_handler]
+fn panic(_info: &PanicInfo) -> ! {
+    loop {}
+}
+
+/// Distance to the wall straight ahead of the middle of a 3x3 room, in Q16.16
+#[no_mangle]
+pub extern "C" fn distance_ahead() -> i32 {
+    let tiles = [1, 1, 1, 1, 0, 1, 1, 1, 1];
+    let board = match FixedBoard::new(&tiles, 3, 3) {
+        Some(board) => board,
+        None => return -1,
+    };
+    let middle = fixed::from_int(1) + fixed::HALF;
+    cast_ray(&board, middle, middle, Angle::new(0)).map_or(-1, |hit| hit.distance)
+}
@@ -0,0 +1,54 @@
+use core::ops::{Add, Sub};
+
+pub const ANGLE_BITS: u32 = 12;
+/// Binary angle steps in one full turn
+pub const FULL_TURN: u16 = 1 << ANGLE_BITS;
+pub const HALF_TURN: u16 = FULL_TURN / 2;
+pub const QUARTER_TURN: u16 = FULL_TURN / 4;
+const MASK: u16 = FULL_TURN - 1;
+
+/// Binary angle, `FULL_TURN` steps per revolution, clockwise like `ray.rs`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct Angle(u16);
+
+impl Angle {
+    pub const fn new(steps: u16) -> Angle {
+        Angle(steps & MASK)
+    }
+
+    pub const fn steps(self) -> u16 {
+        self.0
+    }
+
+    pub const fn quarter_turn() -> Angle {
+        Angle(QUARTER_TURN)
+    }
+
+    pub const fn half_turn() -> Angle {
+        Angle(HALF_TURN)
+    }
+
+    /// `self * numerator / denominator`, for splitting a fov into columns
+    pub fn scale(self, numerator: usize, denominator: usize) -> Angle {
+        if denominator == 0 {
+            return Angle(0);
+        }
+        Angle::new((self.0 as usize * numerator / denominator) as u16)
+    }
+}
+
+impl Add for Angle {
+    type Output = Angle;
+
+    fn add(self, other: Angle) -> Angle {
+        Angle::new(self.0.wrapping_add(other.0))
+    }
+}
+
+impl Sub for Angle {
+    type Output = Angle;
+
+    fn sub(self, other: Angle) -> Angle {
+        Angle::new(self.0.wrapping_sub(other.0))
+    }
+}
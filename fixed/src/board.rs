@@ -0,0 +1,54 @@
+use fixed::{self, Fixed};
+
+/// Borrowed tile grid, `0` is empty and anything else is a wall
+#[derive(Debug, Clone, Copy)]
+pub struct FixedBoard<'a> {
+    tiles: &'a [u32],
+    tiles_x: usize,
+    tiles_y: usize,
+}
+
+impl<'a> FixedBoard<'a> {
+    /// `None` when `tiles` doesn't hold exactly `tiles_x * tiles_y` entries
+    pub fn new(tiles: &'a [u32], tiles_x: usize, tiles_y: usize) -> Option<FixedBoard<'a>> {
+        if tiles.len() != tiles_x * tiles_y {
+            return None;
+        }
+        Some(FixedBoard {
+            tiles,
+            tiles_x,
+            tiles_y,
+        })
+    }
+
+    pub fn tiles_x(&self) -> usize {
+        self.tiles_x
+    }
+
+    pub fn tiles_y(&self) -> usize {
+        self.tiles_y
+    }
+
+    pub fn get_index(&self, x_tile: i32, y_tile: i32) -> Option<usize> {
+        if x_tile < 0 || y_tile < 0 {
+            return None;
+        }
+        let (x_tile, y_tile) = (x_tile as usize, y_tile as usize);
+        if x_tile >= self.tiles_x || y_tile >= self.tiles_y {
+            return None;
+        }
+        Some(y_tile * self.tiles_x + x_tile)
+    }
+
+    pub fn tile(&self, board_index: usize) -> u32 {
+        self.tiles[board_index]
+    }
+
+    /// Anything outside the board counts as a wall
+    pub fn is_wall_at(&self, x: Fixed, y: Fixed) -> bool {
+        match self.get_index(fixed::to_int(x), fixed::to_int(y)) {
+            Some(board_index) => self.tiles[board_index] != 0,
+            None => true,
+        }
+    }
+}
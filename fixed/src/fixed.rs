@@ -0,0 +1,130 @@
+/// Q16.16 signed fixed point number
+pub type Fixed = i32;
+
+pub const FRAC_BITS: u32 = 16;
+pub const ONE: Fixed = 1 << FRAC_BITS;
+pub const HALF: Fixed = ONE / 2;
+
+pub const fn from_int(value: i32) -> Fixed {
+    value << FRAC_BITS
+}
+
+/// Rounds towards negative infinity
+pub const fn to_int(value: Fixed) -> i32 {
+    value >> FRAC_BITS
+}
+
+/// `numerator / denominator` as a fixed point value
+pub const fn from_ratio(numerator: i32, denominator: i32) -> Fixed {
+    (((numerator as i64) << FRAC_BITS) / denominator as i64) as Fixed
+}
+
+pub const fn floor(value: Fixed) -> Fixed {
+    value & !(ONE - 1)
+}
+
+pub const fn ceil(value: Fixed) -> Fixed {
+    floor(value + ONE - 1)
+}
+
+pub const fn fract(value: Fixed) -> Fixed {
+    value & (ONE - 1)
+}
+
+pub fn mul(left: Fixed, right: Fixed) -> Fixed {
+    saturate((i64::from(left) * i64::from(right)) >> FRAC_BITS)
+}
+
+pub fn div(left: Fixed, right: Fixed) -> Fixed {
+    if right == 0 {
+        return if left < 0 { Fixed::MIN } else { Fixed::MAX };
+    }
+    saturate((i64::from(left) << FRAC_BITS) / i64::from(right))
+}
+
+pub fn saturate(value: i64) -> Fixed {
+    if value > i64::from(Fixed::MAX) {
+        Fixed::MAX
+    } else if value < i64::from(Fixed::MIN) {
+        Fixed::MIN
+    } else {
+        value as Fixed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+
+    fn to_f64(value: Fixed) -> f64 {
+        f64::from(value) / f64::from(ONE)
+    }
+
+    fn from_f64(value: f64) -> Fixed {
+        (value * f64::from(ONE)).round() as Fixed
+    }
+
+    /// One step of the last fractional bit
+    const ULP: f64 = 1.0 / 65536.0;
+
+    /// Products of any two stay inside the Q16.16 range
+    const SAMPLES: [f64; 9] = [-150.5, -7.5, -1.0, -0.001, 0.0, 0.3, 1.0, 2.75, 180.125];
+
+    #[test]
+    fn mul_matches_f64() {
+        for left in SAMPLES.iter().map(|&value| to_f64(from_f64(value))) {
+            for right in SAMPLES.iter().map(|&value| to_f64(from_f64(value))) {
+                let product = to_f64(mul(from_f64(left), from_f64(right)));
+                assert!(
+                    (product - left * right).abs() < ULP,
+                    "{} * {} gave {}",
+                    left,
+                    right,
+                    product
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn div_matches_f64() {
+        let quantised = SAMPLES.iter().map(|&value| to_f64(from_f64(value)));
+        for left in quantised.clone() {
+            for right in quantised.clone().filter(|&right| right.abs() >= 0.25) {
+                let quotient = to_f64(div(from_f64(left), from_f64(right)));
+                assert!(
+                    (quotient - left / right).abs() < ULP,
+                    "{} / {} gave {}",
+                    left,
+                    right,
+                    quotient
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn out_of_range_results_saturate() {
+        assert_eq!(mul(from_int(30_000), from_int(30_000)), Fixed::MAX);
+        assert_eq!(mul(from_int(-30_000), from_int(30_000)), Fixed::MIN);
+        assert_eq!(div(ONE, 0), Fixed::MAX);
+        assert_eq!(div(-ONE, 0), Fixed::MIN);
+        assert_eq!(div(from_int(20_000), HALF / 100), Fixed::MAX);
+    }
+
+    #[test]
+    fn rounding_helpers_match_f64() {
+        for &value in SAMPLES.iter() {
+            let fixed = from_f64(value);
+            let value = to_f64(fixed);
+            assert_eq!(to_f64(floor(fixed)), value.floor());
+            assert_eq!(to_f64(ceil(fixed)), value.ceil());
+            assert_eq!(to_int(fixed), value.floor() as i32);
+            assert_eq!(to_f64(fract(fixed)), value - value.floor());
+        }
+        assert_eq!(from_ratio(3, 4), from_f64(0.75));
+        assert_eq!(from_ratio(-1, 2), -HALF);
+    }
+}
@@ -0,0 +1,19 @@
+//! Fixed point port of the ray casting core
+//!
+//! Uses Q16.16 integers and compile time trig tables only, so it builds
+//! without `std`, floating point or an allocator. Distances and positions
+//! are in tiles rather than screen pixels.
+#![no_std]
+
+pub mod angle;
+pub mod board;
+pub mod fixed;
+pub mod player;
+pub mod ray;
+pub mod tables;
+
+pub use angle::Angle;
+pub use board::FixedBoard;
+pub use fixed::Fixed;
+pub use player::FixedPlayer;
+pub use ray::{cast_ray, FixedHit, Side};
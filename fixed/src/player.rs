@@ -0,0 +1,81 @@
+use angle::Angle;
+use board::FixedBoard;
+use fixed::{self, Fixed};
+use ray::{cast_ray, FixedHit};
+use tables;
+
+#[derive(Debug, Clone, Copy)]
+pub struct FixedPlayer {
+    pub x: Fixed,
+    pub y: Fixed,
+    pub angle: Angle,
+    pub angle_tick: Angle,
+    pub move_step: Fixed,
+}
+
+impl FixedPlayer {
+    /// Angle of `column` when `columns` rays are spread evenly across `fov`
+    pub fn ray_angle(&self, fov: Angle, column: usize, columns: usize) -> Angle {
+        if columns > 1 {
+            let start = self.angle - fov.scale(1, 2);
+            start + fov.scale(column, columns - 1)
+        } else {
+            self.angle
+        }
+    }
+
+    /// Casts one ray per entry of `hits`, the caller owns the buffer
+    pub fn cast(&self, board: &FixedBoard, fov: Angle, hits: &mut [Option<FixedHit>]) {
+        let columns = hits.len();
+        for (column, hit) in hits.iter_mut().enumerate() {
+            let angle = self.ray_angle(fov, column, columns);
+            *hit = cast_ray(board, self.x, self.y, angle);
+        }
+    }
+
+    pub fn turn_left(&mut self) {
+        self.angle = self.angle - self.angle_tick;
+    }
+
+    pub fn turn_right(&mut self) {
+        self.angle = self.angle + self.angle_tick;
+    }
+
+    pub fn move_forward(&mut self, board: &FixedBoard) {
+        let angle = self.angle;
+        self.move_angle(angle, board);
+    }
+
+    pub fn move_backward(&mut self, board: &FixedBoard) {
+        let angle = self.angle + Angle::half_turn();
+        self.move_angle(angle, board);
+    }
+
+    pub fn strafe_left(&mut self, board: &FixedBoard) {
+        let angle = self.angle - Angle::quarter_turn();
+        self.move_angle(angle, board);
+    }
+
+    pub fn strafe_right(&mut self, board: &FixedBoard) {
+        let angle = self.angle + Angle::quarter_turn();
+        self.move_angle(angle, board);
+    }
+
+    fn move_angle(&mut self, angle: Angle, board: &FixedBoard) {
+        let new_x = self.x + fixed::mul(tables::cos(angle), self.move_step);
+        let new_y = self.y + fixed::mul(tables::sin(angle), self.move_step);
+        if board.is_wall_at(new_x, new_y) {
+            return;
+        }
+        self.x = new_x;
+        self.y = new_y;
+    }
+}
+
+/// Column height in pixels for a wall `distance` tiles away
+pub fn wall_height(distance: Fixed, projection: i32) -> i32 {
+    if distance <= 0 {
+        return projection;
+    }
+    fixed::to_int(fixed::div(fixed::from_int(projection), distance))
+}
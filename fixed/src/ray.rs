@@ -0,0 +1,287 @@
+use angle::Angle;
+use board::FixedBoard;
+use fixed::{self, Fixed, ONE};
+use tables;
+
+/// Which grid line the ray crossed when it hit the wall
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    /// a line of constant y, `InterceptType::XIntercept` in `ray.rs`
+    Horizontal,
+    /// a line of constant x, `InterceptType::YIntercept` in `ray.rs`
+    Vertical,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedHit {
+    pub board_index: usize,
+    pub x: Fixed,
+    pub y: Fixed,
+    /// distance from the ray start along the ray, in tiles
+    pub distance: Fixed,
+    pub side: Side,
+}
+
+/// Casts a single ray from `(x, y)`, `None` when it leaves the board
+pub fn cast_ray(board: &FixedBoard, x: Fixed, y: Fixed, angle: Angle) -> Option<FixedHit> {
+    let sin = tables::sin(angle);
+    let cos = tables::cos(angle);
+    let horizontal = if sin != 0 {
+        horizontal_hit(board, x, y, angle, sin, cos)
+    } else {
+        None
+    };
+    let vertical = if cos != 0 {
+        vertical_hit(board, x, y, angle, sin, cos)
+    } else {
+        None
+    };
+
+    match (horizontal, vertical) {
+        (Some(horizontal), Some(vertical)) => {
+            if horizontal.distance < vertical.distance {
+                Some(horizontal)
+            } else {
+                Some(vertical)
+            }
+        }
+        (horizontal, None) => horizontal,
+        (None, vertical) => vertical,
+    }
+}
+
+/// Walks the crossings with horizontal grid lines
+fn horizontal_hit(
+    board: &FixedBoard,
+    x: Fixed,
+    y: Fixed,
+    angle: Angle,
+    sin: Fixed,
+    cos: Fixed,
+) -> Option<FixedHit> {
+    let cot = i64::from(tables::cot(angle));
+    let (mut grid_y, step_y) = if sin > 0 {
+        (i64::from(fixed::floor(y) + ONE), i64::from(ONE))
+    } else {
+        (i64::from(fixed::ceil(y) - ONE), -i64::from(ONE))
+    };
+    let mut hit_x = i64::from(x) + ((cot * (grid_y - i64::from(y))) >> fixed::FRAC_BITS);
+    let step_x = (cot * step_y) >> fixed::FRAC_BITS;
+
+    for _ in 0..=board.tiles_y() {
+        let mut x_tile = to_tile(hit_x)?;
+        // exactly on a corner heading left, the wall is in the tile to the left
+        if hit_x & i64::from(ONE - 1) == 0 && cos < 0 {
+            x_tile -= 1;
+        }
+        let mut y_tile = to_tile(grid_y)?;
+        if sin < 0 {
+            y_tile -= 1;
+        }
+        let board_index = board.get_index(x_tile, y_tile)?;
+        if board.tile(board_index) != 0 {
+            return Some(hit(
+                board_index,
+                x,
+                y,
+                hit_x,
+                grid_y,
+                sin,
+                cos,
+                Side::Horizontal,
+            ));
+        }
+        grid_y += step_y;
+        hit_x += step_x;
+    }
+    None
+}
+
+/// Walks the crossings with vertical grid lines
+fn vertical_hit(
+    board: &FixedBoard,
+    x: Fixed,
+    y: Fixed,
+    angle: Angle,
+    sin: Fixed,
+    cos: Fixed,
+) -> Option<FixedHit> {
+    let tan = i64::from(tables::tan(angle));
+    let (mut grid_x, step_x) = if cos > 0 {
+        (i64::from(fixed::floor(x) + ONE), i64::from(ONE))
+    } else {
+        (i64::from(fixed::ceil(x) - ONE), -i64::from(ONE))
+    };
+    let mut hit_y = i64::from(y) + ((tan * (grid_x - i64::from(x))) >> fixed::FRAC_BITS);
+    let step_y = (tan * step_x) >> fixed::FRAC_BITS;
+
+    for _ in 0..=board.tiles_x() {
+        let mut x_tile = to_tile(grid_x)?;
+        if cos < 0 {
+            x_tile -= 1;
+        }
+        let mut y_tile = to_tile(hit_y)?;
+        // exactly on a corner heading up, the wall is in the tile above
+        if hit_y & i64::from(ONE - 1) == 0 && sin < 0 {
+            y_tile -= 1;
+        }
+        let board_index = board.get_index(x_tile, y_tile)?;
+        if board.tile(board_index) != 0 {
+            return Some(hit(
+                board_index,
+                x,
+                y,
+                grid_x,
+                hit_y,
+                sin,
+                cos,
+                Side::Vertical,
+            ));
+        }
+        grid_x += step_x;
+        hit_y += step_y;
+    }
+    None
+}
+
+/// Tile coordinate of a wide fixed point value, `None` once it is far outside any board
+fn to_tile(value: i64) -> Option<i32> {
+    let tile = value >> fixed::FRAC_BITS;
+    if tile < i64::from(i32::MIN) || tile > i64::from(i32::MAX) {
+        return None;
+    }
+    Some(tile as i32)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn hit(
+    board_index: usize,
+    x: Fixed,
+    y: Fixed,
+    hit_x: i64,
+    hit_y: i64,
+    sin: Fixed,
+    cos: Fixed,
+    side: Side,
+) -> FixedHit {
+    // projecting onto the ray direction gives the euclidean length without a sqrt
+    let dx = hit_x - i64::from(x);
+    let dy = hit_y - i64::from(y);
+    let distance = (dx * i64::from(cos) + dy * i64::from(sin)) >> fixed::FRAC_BITS;
+    FixedHit {
+        board_index,
+        x: fixed::saturate(hit_x),
+        y: fixed::saturate(hit_y),
+        distance: fixed::saturate(distance),
+        side,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use angle::FULL_TURN;
+
+    #[rustfmt::skip]
+    const TILES: [u32; 48] = [
+        1, 1, 1, 1, 1, 1, 1, 1,
+        1, 0, 0, 0, 0, 0, 0, 1,
+        1, 0, 2, 0, 0, 3, 0, 1,
+        1, 0, 0, 0, 0, 0, 0, 1,
+        1, 0, 0, 0, 4, 0, 0, 1,
+        1, 1, 1, 1, 1, 1, 1, 1,
+    ];
+
+    fn to_f64(value: Fixed) -> f64 {
+        f64::from(value) / f64::from(ONE)
+    }
+
+    /// Tile index, distance and side of the first wall found by walking the
+    /// grid in f64, `None` when the ray passes too close to a corner to tell
+    fn grid_walk(x: f64, y: f64, radians: f64) -> Option<(usize, f64, Side)> {
+        let (sin, cos) = radians.sin_cos();
+        let (mut x_tile, mut y_tile) = (x.floor() as i64, y.floor() as i64);
+        let first = |start: f64, tile: i64, delta: f64| {
+            if delta > 0.0 {
+                (tile as f64 + 1.0 - start) / delta
+            } else if delta < 0.0 {
+                (tile as f64 - start) / delta
+            } else {
+                f64::INFINITY
+            }
+        };
+        let (mut next_x, mut next_y) = (first(x, x_tile, cos), first(y, y_tile, sin));
+        loop {
+            if (next_x - next_y).abs() < 1e-3 {
+                return None;
+            }
+            let (distance, side) = if next_x < next_y {
+                x_tile += cos.signum() as i64;
+                next_x += 1.0 / cos.abs();
+                (next_x - 1.0 / cos.abs(), Side::Vertical)
+            } else {
+                y_tile += sin.signum() as i64;
+                next_y += 1.0 / sin.abs();
+                (next_y - 1.0 / sin.abs(), Side::Horizontal)
+            };
+            let index = (y_tile * 8 + x_tile) as usize;
+            if TILES[index] != 0 {
+                return Some((index, distance, side));
+            }
+        }
+    }
+
+    #[test]
+    fn hits_match_an_f64_grid_walk() {
+        let board = FixedBoard::new(&TILES, 8, 6).expect("board");
+        let starts = [
+            (1.5, 1.5),
+            (3.25, 3.75),
+            (6.9, 1.1),
+            (4.5, 2.5),
+            (1.01, 4.99),
+        ];
+        for &(x, y) in starts.iter() {
+            for steps in (0..FULL_TURN).step_by(5) {
+                let radians = f64::from(steps) / f64::from(FULL_TURN) * 2.0 * std::f64::consts::PI;
+                let expected = match grid_walk(x, y, radians) {
+                    Some(expected) => expected,
+                    None => continue,
+                };
+                let hit = cast_ray(
+                    &board,
+                    (x * f64::from(ONE)) as Fixed,
+                    (y * f64::from(ONE)) as Fixed,
+                    Angle::new(steps),
+                )
+                .expect("closed room");
+                assert_eq!(
+                    hit.board_index, expected.0,
+                    "from ({}, {}) at {}",
+                    x, y, steps
+                );
+                assert_eq!(hit.side, expected.2, "from ({}, {}) at {}", x, y, steps);
+                let error = (to_f64(hit.distance) - expected.1).abs();
+                assert!(
+                    error < 1e-3 * (1.0 + expected.1),
+                    "from ({}, {}) at {} off by {}",
+                    x,
+                    y,
+                    steps,
+                    error
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn rays_leaving_the_board_miss() {
+        let tiles = [0, 0, 0, 0];
+        let board = FixedBoard::new(&tiles, 2, 2).expect("board");
+        for steps in (0..FULL_TURN).step_by(64) {
+            assert_eq!(cast_ray(&board, ONE, ONE, Angle::new(steps)), None);
+        }
+    }
+}
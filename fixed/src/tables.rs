@@ -0,0 +1,161 @@
+//! Trig lookup tables, generated at compile time with integer maths only
+
+use angle::{Angle, HALF_TURN, QUARTER_TURN};
+use fixed::{Fixed, FRAC_BITS};
+
+/// Working precision of the table generator
+const WORK_BITS: u32 = 30;
+/// PI / 2 in Q2.30
+const HALF_PI: i128 = 1_686_629_713;
+
+/// sin(i * PI / 2 / QUARTER_TURN) for i in 0..=QUARTER_TURN
+static QUARTER_SIN: [Fixed; QUARTER_TURN as usize + 1] = build_quarter_sin();
+/// tan(i * PI / HALF_TURN) for i in 0..HALF_TURN, saturated at the poles
+static TAN: [Fixed; HALF_TURN as usize] = build_tan();
+
+/// Taylor series for 0 <= x <= PI / 2, x in Q2.30
+const fn sin_work(x: i128) -> i128 {
+    let x_squared = (x * x) >> WORK_BITS;
+    let mut term = x;
+    let mut sum = x;
+    let mut n = 1;
+    while n < 12 {
+        term = -((term * x_squared) >> WORK_BITS) / ((2 * n) * (2 * n + 1));
+        sum += term;
+        n += 1;
+    }
+    sum
+}
+
+const fn quarter_radians(steps: i128) -> i128 {
+    HALF_PI * steps / QUARTER_TURN as i128
+}
+
+const fn round_to_fixed(work: i128) -> Fixed {
+    let shift = WORK_BITS - FRAC_BITS;
+    ((work + (1 << (shift - 1))) >> shift) as Fixed
+}
+
+const fn build_quarter_sin() -> [Fixed; QUARTER_TURN as usize + 1] {
+    let mut table = [0; QUARTER_TURN as usize + 1];
+    let mut i = 0;
+    while i <= QUARTER_TURN as usize {
+        table[i] = round_to_fixed(sin_work(quarter_radians(i as i128)));
+        i += 1;
+    }
+    table
+}
+
+const fn build_tan() -> [Fixed; HALF_TURN as usize] {
+    let mut table = [0; HALF_TURN as usize];
+    let mut i = 0;
+    while i < QUARTER_TURN as usize {
+        let sin = sin_work(quarter_radians(i as i128));
+        let cos = sin_work(quarter_radians(QUARTER_TURN as i128 - i as i128));
+        let tan = (sin << FRAC_BITS) / cos;
+        let tan = if tan > Fixed::MAX as i128 {
+            Fixed::MAX
+        } else {
+            tan as Fixed
+        };
+        table[i] = tan;
+        if i > 0 {
+            // tan(PI - x) = -tan(x)
+            table[HALF_TURN as usize - i] = -tan;
+        }
+        i += 1;
+    }
+    table[QUARTER_TURN as usize] = Fixed::MAX;
+    table
+}
+
+pub fn sin(angle: Angle) -> Fixed {
+    let steps = angle.steps();
+    let quarter = QUARTER_TURN;
+    match steps / quarter {
+        0 => QUARTER_SIN[steps as usize],
+        1 => QUARTER_SIN[(2 * quarter - steps) as usize],
+        2 => -QUARTER_SIN[(steps - 2 * quarter) as usize],
+        _ => -QUARTER_SIN[(4 * quarter - steps) as usize],
+    }
+}
+
+pub fn cos(angle: Angle) -> Fixed {
+    sin(angle + Angle::quarter_turn())
+}
+
+pub fn tan(angle: Angle) -> Fixed {
+    TAN[(angle.steps() % HALF_TURN) as usize]
+}
+
+/// cot(x) = tan(PI / 2 - x)
+pub fn cot(angle: Angle) -> Fixed {
+    tan(Angle::quarter_turn() - angle)
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use angle::FULL_TURN;
+    use fixed::ONE;
+
+    fn radians(angle: Angle) -> f64 {
+        f64::from(angle.steps()) / f64::from(FULL_TURN) * 2.0 * std::f64::consts::PI
+    }
+
+    fn to_f64(value: Fixed) -> f64 {
+        f64::from(value) / f64::from(ONE)
+    }
+
+    #[test]
+    fn sin_and_cos_match_f64_all_the_way_round() {
+        for steps in 0..FULL_TURN {
+            let angle = Angle::new(steps);
+            let (expected_sin, expected_cos) = radians(angle).sin_cos();
+            assert!(
+                (to_f64(sin(angle)) - expected_sin).abs() < 2e-5,
+                "sin at {}",
+                steps
+            );
+            assert!(
+                (to_f64(cos(angle)) - expected_cos).abs() < 2e-5,
+                "cos at {}",
+                steps
+            );
+        }
+    }
+
+    #[test]
+    fn tan_and_cot_match_f64_away_from_the_poles() {
+        for steps in 0..FULL_TURN {
+            let angle = Angle::new(steps);
+            let expected_tan = radians(angle).tan();
+            if expected_tan.abs() < 100.0 {
+                let error = (to_f64(tan(angle)) - expected_tan).abs();
+                assert!(
+                    error < 1e-4 * (1.0 + expected_tan.abs()),
+                    "tan at {}",
+                    steps
+                );
+            }
+            let expected_cot = 1.0 / expected_tan;
+            if expected_cot.abs() < 100.0 {
+                let error = (to_f64(cot(angle)) - expected_cot).abs();
+                assert!(
+                    error < 1e-4 * (1.0 + expected_cot.abs()),
+                    "cot at {}",
+                    steps
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn tan_saturates_at_the_poles() {
+        assert_eq!(tan(Angle::quarter_turn()), Fixed::MAX);
+        assert_eq!(cot(Angle::new(0)), Fixed::MAX);
+        assert_eq!(cot(Angle::half_turn()), Fixed::MAX);
+    }
+}
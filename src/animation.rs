@@ -0,0 +1,190 @@
+use image::RgbaImage;
+use palette::Palette;
+use std::collections::HashMap;
+
+/// One step of an animated tile
+pub enum TileFrame {
+    Color([f32; 4]),
+    Texture {
+        image: RgbaImage,
+        /// mean colour of `image`
+        average: [f32; 4],
+        /// palette entry of every texel, empty until `index_texels` runs
+        indices: Vec<u8>,
+    },
+}
+
+impl TileFrame {
+    pub fn texture(image: RgbaImage) -> TileFrame {
+        let mut sum = [0.0f32; 4];
+        for pixel in image.pixels() {
+            for (total, channel) in sum.iter_mut().zip(to_color(pixel.0).iter()) {
+                *total += channel;
+            }
+        }
+        let count = (image.width() * image.height()).max(1) as f32;
+        let average = [
+            sum[0] / count,
+            sum[1] / count,
+            sum[2] / count,
+            sum[3] / count,
+        ];
+        TileFrame::Texture {
+            image,
+            average,
+            indices: Vec::new(),
+        }
+    }
+
+    /// Looks up the palette entry of every texel once, for the indexed renderer
+    pub fn index_texels(&mut self, palette: &Palette) {
+        if let TileFrame::Texture { image, indices, .. } = self {
+            // textures use far fewer colours than texels
+            let mut nearest: HashMap<[u8; 3], u8> = HashMap::new();
+            *indices = image
+                .pixels()
+                .map(|pixel| {
+                    let [r, g, b, _] = pixel.0;
+                    *nearest
+                        .entry([r, g, b])
+                        .or_insert_with(|| palette.nearest([r, g, b]))
+                })
+                .collect();
+        }
+    }
+
+    /// Colour at `u` across the wall face and `v` down it, both in `0.0..1.0`
+    pub fn sample(&self, u: f64, v: f64) -> [f32; 4] {
+        match self {
+            TileFrame::Color(color) => *color,
+            TileFrame::Texture { image, .. } => {
+                let (x, y) = texel(image, u, v);
+                to_color(image.get_pixel(x, y).0)
+            }
+        }
+    }
+
+    /// Palette entry at `u` and `v` once `index_texels` ran, `None` for colour
+    /// frames and mostly transparent texels, which indexed pixels can't blend
+    pub fn sample_index(&self, u: f64, v: f64) -> Option<u8> {
+        match self {
+            TileFrame::Color(_) => None,
+            TileFrame::Texture { image, indices, .. } => {
+                let (x, y) = texel(image, u, v);
+                if image.get_pixel(x, y).0[3] < 128 {
+                    return None;
+                }
+                indices.get((y * image.width() + x) as usize).cloned()
+            }
+        }
+    }
+
+    /// Single colour standing in for the frame where a texture can't be drawn
+    pub fn average(&self) -> [f32; 4] {
+        match self {
+            TileFrame::Color(color) => *color,
+            TileFrame::Texture { average, .. } => *average,
+        }
+    }
+}
+
+/// Frames shown one after another, `fps` times a second
+pub struct TileAnimation {
+    pub frames: Vec<TileFrame>,
+    pub fps: f64,
+}
+
+impl TileAnimation {
+    pub fn frame_at(&self, time: f64) -> &TileFrame {
+        let step = (time * self.fps).max(0.0) as usize;
+        &self.frames[step % self.frames.len()]
+    }
+}
+
+/// Animated tile ids and the clock they run on
+pub struct TileAnimations {
+    animations: HashMap<u32, TileAnimation>,
+    /// seconds of `dt` seen so far
+    time: f64,
+}
+
+impl TileAnimations {
+    pub fn new() -> TileAnimations {
+        TileAnimations {
+            animations: HashMap::new(),
+            time: 0.0,
+        }
+    }
+
+    /// Adds or replaces the animation for `tile`, an animation without frames is ignored
+    pub fn insert(&mut self, tile: u32, animation: TileAnimation) {
+        if !animation.frames.is_empty() {
+            self.animations.insert(tile, animation);
+        }
+    }
+
+    pub fn update(&mut self, dt: f64) {
+        self.time += dt;
+    }
+
+    /// Seconds of `dt` seen so far, for animations kept elsewhere to run on
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    /// Current frame of `tile`, `None` if it isn't animated
+    pub fn frame(&self, tile: u32) -> Option<&TileFrame> {
+        self.animations
+            .get(&tile)
+            .map(|animation| animation.frame_at(self.time))
+    }
+
+    /// Every frame of every animation
+    pub fn frames_mut(&mut self) -> impl Iterator<Item = &mut TileFrame> {
+        self.animations
+            .values_mut()
+            .flat_map(|animation| animation.frames.iter_mut())
+    }
+}
+
+/// Texel of `image` at `u` across and `v` down, both in `0.0..1.0`
+fn texel(image: &RgbaImage, u: f64, v: f64) -> (u32, u32) {
+    let x = ((u * f64::from(image.width())) as u32).min(image.width() - 1);
+    let y = ((v * f64::from(image.height())) as u32).min(image.height() - 1);
+    (x, y)
+}
+
+fn to_color(pixel: [u8; 4]) -> [f32; 4] {
+    [
+        f32::from(pixel[0]) / 255.0,
+        f32::from(pixel[1]) / 255.0,
+        f32::from(pixel[2]) / 255.0,
+        f32::from(pixel[3]) / 255.0,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    #[test]
+    fn texels_keep_their_own_palette_entries() {
+        // entry i is grey level i
+        let grey: Vec<u8> = (0..=255u8).flat_map(|i| vec![i, i, i]).collect();
+        let palette = Palette::from_raw(&grey).unwrap();
+        let mut image = RgbaImage::new(3, 1);
+        image.put_pixel(0, 0, Rgba([200, 200, 200, 255]));
+        image.put_pixel(1, 0, Rgba([12, 10, 8, 255]));
+        image.put_pixel(2, 0, Rgba([90, 90, 90, 20]));
+        let mut frame = TileFrame::texture(image);
+        assert_eq!(frame.sample_index(0.1, 0.5), None);
+
+        frame.index_texels(&palette);
+        assert_eq!(frame.sample_index(0.1, 0.5), Some(200));
+        assert_eq!(frame.sample_index(0.5, 0.5), Some(10));
+        // mostly transparent texels are left for whatever is behind
+        assert_eq!(frame.sample_index(0.9, 0.5), None);
+        assert_eq!(TileFrame::Color([1.0; 4]).sample_index(0.5, 0.5), None);
+    }
+}
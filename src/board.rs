@@ -1,26 +1,248 @@
+use animation::{TileAnimations, TileFrame};
 use colors;
+use decal::Decals;
 use graphics::{math::Matrix2d, Transformed};
+use map::document::MapInfo;
 use maths;
+use palette::Palette;
 use point::{BoardPoint, ScreenPoint};
 use sharp_graphics::SharpGraphics;
+use tiles::TileRegistry;
+
+/// A tile id read from the board, 0 is empty floor and anything else a wall
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Tile(pub u32);
+
+impl Tile {
+    pub const EMPTY: Tile = Tile(0);
+    /// wall standing in for everything past the edge under `OutOfBounds::Solid`,
+    /// it has no colour so nothing is drawn for it
+    pub const EDGE: Tile = Tile(u32::MAX);
+
+    pub fn id(self) -> u32 {
+        self.0
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn is_wall(self) -> bool {
+        !self.is_empty()
+    }
+}
+
+/// What point queries and rays see past the edge of the board
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutOfBounds {
+    /// a wall, so nothing walks off the board
+    Solid,
+    /// open floor
+    Empty,
+    /// the opposite edge, the board repeats in every direction
+    Wrap,
+}
+
+impl OutOfBounds {
+    pub fn parse(value: &str) -> Option<OutOfBounds> {
+        match value {
+            "solid" => Some(OutOfBounds::Solid),
+            "empty" => Some(OutOfBounds::Empty),
+            "wrap" => Some(OutOfBounds::Wrap),
+            _ => None,
+        }
+    }
+}
+
+/// One of the grids a board is made of, all `tiles_x` by `tiles_y` ids
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BoardLayer {
+    /// what rays and players run into, 0 is open
+    Walls,
+    /// look of the floor, 0 shows the map's floor colour
+    Floor,
+    /// look of the ceiling, 0 shows the map's ceiling colour and tile kinds
+    /// marked `sky` open it to the sky
+    Ceiling,
+    /// markers for items and triggers, 0 is none, nothing collides with them
+    Objects,
+}
+
+impl BoardLayer {
+    pub const ALL: [BoardLayer; 4] = [
+        BoardLayer::Walls,
+        BoardLayer::Floor,
+        BoardLayer::Ceiling,
+        BoardLayer::Objects,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BoardLayer::Walls => "walls",
+            BoardLayer::Floor => "floor",
+            BoardLayer::Ceiling => "ceiling",
+            BoardLayer::Objects => "objects",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<BoardLayer> {
+        BoardLayer::ALL
+            .iter()
+            .cloned()
+            .find(|layer| layer.name().eq_ignore_ascii_case(value))
+    }
+
+    /// The layer after this one, back to walls after objects
+    pub fn next(self) -> BoardLayer {
+        let i = BoardLayer::ALL
+            .iter()
+            .position(|&layer| layer == self)
+            .expect("layer in ALL");
+        BoardLayer::ALL[(i + 1) % BoardLayer::ALL.len()]
+    }
+}
 
 pub struct Board {
+    /// the walls layer
     pub tiles: Vec<u32>,
+    pub floor_tiles: Vec<u32>,
+    pub ceiling_tiles: Vec<u32>,
+    pub object_tiles: Vec<u32>,
     pub tiles_x: usize,
     pub block_size: f64,
     pub tiles_y: usize,
+    pub animations: TileAnimations,
+    pub decals: Decals,
+    /// name, colours and fog from the map file
+    pub info: MapInfo,
+    pub out_of_bounds: OutOfBounds,
+    pub registry: TileRegistry,
 }
 
 impl Board {
     pub fn new(tiles: Vec<u32>, tiles_x: usize, tiles_y: usize, block_size: f64) -> Board {
+        let size = tiles_x * tiles_y;
         Board {
-            tiles: tiles,
-            tiles_x: tiles_x,
-            block_size: block_size,
-            tiles_y: tiles_y,
+            tiles,
+            floor_tiles: vec![0; size],
+            ceiling_tiles: vec![0; size],
+            object_tiles: vec![0; size],
+            tiles_x,
+            block_size,
+            tiles_y,
+            animations: TileAnimations::new(),
+            decals: Decals::new(),
+            info: MapInfo::default(),
+            out_of_bounds: OutOfBounds::Solid,
+            registry: TileRegistry::default(),
+        }
+    }
+
+    /// Looks up the palette entry of every texel of every texture, so the
+    /// indexed renderer never searches the palette while drawing
+    pub fn index_textures(&mut self, palette: &Palette) {
+        for frame in self
+            .animations
+            .frames_mut()
+            .chain(self.registry.frames_mut())
+        {
+            frame.index_texels(palette);
+        }
+    }
+
+    /// Advances tile animations by `dt` seconds
+    pub fn update(&mut self, dt: f64) {
+        self.animations.update(dt);
+    }
+
+    /// Current frame of `tile` when it is animated, the map's own textures
+    /// before the registry's animations
+    fn animation_frame(&self, tile: u32) -> Option<&TileFrame> {
+        self.animations.frame(tile).or_else(|| {
+            self.registry
+                .animation(tile)
+                .map(|animation| animation.frame_at(self.animations.time()))
+        })
+    }
+
+    /// Current colour of `tile` on the 2d map and the mini map, the average of
+    /// the frame for textured animations, `None` for empty or unknown tiles
+    pub fn map_color(&self, tile: u32) -> Option<[f32; 4]> {
+        match self.animation_frame(tile) {
+            Some(frame) => Some(frame.average()),
+            None => self
+                .info
+                .tile_color(tile)
+                .or_else(|| self.registry.map_color(tile)),
         }
     }
 
+    /// Current flat colour of `tile` in the 3d view, textures give their average
+    pub fn wall_color(&self, tile: u32) -> Option<[f32; 4]> {
+        match self.animation_frame(tile) {
+            Some(frame) => Some(frame.average()),
+            None => self
+                .info
+                .tile_color(tile)
+                .or_else(|| self.registry.wall_color(tile)),
+        }
+    }
+
+    /// Animation frame or texture drawn on the walls of `tile`
+    pub fn wall_frame(&self, tile: u32) -> Option<&TileFrame> {
+        if self.info.tile_color(tile).is_some() {
+            return self.animations.frame(tile);
+        }
+        self.animation_frame(tile)
+            .or_else(|| self.registry.texture(tile))
+    }
+
+    /// Ids the registry or the map palette give a colour, in order
+    pub fn known_tiles(&self) -> Vec<u32> {
+        let mut ids = self
+            .registry
+            .ids()
+            .chain(self.info.palette.iter().map(|style| style.id))
+            .filter(|&id| self.wall_color(id).is_some())
+            .collect::<Vec<u32>>();
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+
+    /// Whether `tile` opens the ceiling to the sky
+    pub fn is_sky(&self, tile: u32) -> bool {
+        self.registry.get(tile).is_some_and(|kind| kind.sky)
+    }
+
+    /// Whether players are kept out of `tile`, unknown walls are solid
+    pub fn is_solid(&self, tile: Tile) -> bool {
+        tile.is_wall() && self.registry.get(tile.id()).is_none_or(|kind| kind.solid)
+    }
+
+    /// What a route pays to walk into `tile`, `None` when it is solid
+    pub fn move_cost(&self, tile: Tile) -> Option<f64> {
+        if self.is_solid(tile) {
+            return None;
+        }
+        Some(self.registry.get(tile.id()).map_or(1.0, |kind| kind.cost))
+    }
+
+    /// Whether rays stop at `tile` instead of seeing past it, which takes an
+    /// opaque full height wall
+    pub fn blocks_sight(&self, tile: Tile) -> bool {
+        tile.is_wall()
+            && self
+                .registry
+                .get(tile.id())
+                .is_none_or(|kind| kind.opaque && kind.height >= 1.0)
+    }
+
+    /// Wall height of `tile` as a fraction of a full wall
+    pub fn tile_height(&self, tile: u32) -> f64 {
+        self.registry.get(tile).map_or(1.0, |kind| kind.height)
+    }
+
     pub fn point_from(&self, screen_point: ScreenPoint) -> BoardPoint {
         BoardPoint {
             x: screen_point.x / self.block_size,
@@ -28,14 +250,116 @@ impl Board {
         }
     }
 
-    pub fn get_index(&self, point: BoardPoint) -> usize {
-        let board_x = point.x as usize;
-        let board_y = point.y as usize;
-        self.get_index_from_tile(board_x, board_y)
+    /// Index of the tile holding `point`, `None` off the board
+    pub fn get_index(&self, point: BoardPoint) -> Option<usize> {
+        // NaN fails both comparisons as well
+        if !(point.x >= 0.0 && point.y >= 0.0) {
+            return None;
+        }
+        self.get_index_from_tile(point.x as usize, point.y as usize)
+    }
+
+    /// Index of the tile at column `x_tile`, row `y_tile`, `None` off the board
+    pub fn get_index_from_tile(&self, x_tile: usize, y_tile: usize) -> Option<usize> {
+        if x_tile >= self.tiles_x || y_tile >= self.tiles_y {
+            return None;
+        }
+        Some(y_tile * self.tiles_x + x_tile)
+    }
+
+    /// Index of the tile at column `x_tile`, row `y_tile`, which may be past the
+    /// edge, `None` there unless `out_of_bounds` wraps it back onto the board
+    pub fn wrapped_index(&self, x_tile: i64, y_tile: i64) -> Option<usize> {
+        let (x_tile, y_tile) = if self.out_of_bounds == OutOfBounds::Wrap {
+            (
+                x_tile.rem_euclid(self.tiles_x as i64),
+                y_tile.rem_euclid(self.tiles_y as i64),
+            )
+        } else if x_tile < 0 || y_tile < 0 {
+            return None;
+        } else {
+            (x_tile, y_tile)
+        };
+        self.get_index_from_tile(x_tile as usize, y_tile as usize)
     }
 
-    pub fn get_index_from_tile(&self, x_tile: usize, y_tile: usize) -> usize {
-        y_tile * self.tiles_x + x_tile
+    pub fn tile(&self, board_index: usize) -> Option<Tile> {
+        self.tiles.get(board_index).map(|&id| Tile(id))
+    }
+
+    /// Tile holding `point`, `None` off the board whatever `out_of_bounds` says
+    pub fn tile_at(&self, point: BoardPoint) -> Option<Tile> {
+        self.get_index(point).and_then(|index| self.tile(index))
+    }
+
+    pub fn layer(&self, layer: BoardLayer) -> &[u32] {
+        match layer {
+            BoardLayer::Walls => &self.tiles,
+            BoardLayer::Floor => &self.floor_tiles,
+            BoardLayer::Ceiling => &self.ceiling_tiles,
+            BoardLayer::Objects => &self.object_tiles,
+        }
+    }
+
+    pub fn layer_mut(&mut self, layer: BoardLayer) -> &mut [u32] {
+        match layer {
+            BoardLayer::Walls => &mut self.tiles,
+            BoardLayer::Floor => &mut self.floor_tiles,
+            BoardLayer::Ceiling => &mut self.ceiling_tiles,
+            BoardLayer::Objects => &mut self.object_tiles,
+        }
+    }
+
+    /// Id in `layer` at `point`, `None` off the board
+    pub fn layer_at(&self, layer: BoardLayer, point: BoardPoint) -> Option<u32> {
+        self.get_index(point)
+            .and_then(|index| self.layer(layer).get(index).cloned())
+    }
+
+    /// Puts `tile` in `layer` at `point`, returning false off the board
+    pub fn set_tile(&mut self, layer: BoardLayer, point: BoardPoint, tile: Tile) -> bool {
+        match self.get_index(point) {
+            Some(index) => {
+                self.layer_mut(layer)[index] = tile.id();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Colour on the mini map of whatever is uppermost at `point`, a wall, an
+    /// object or the floor, with `out_of_bounds` deciding what is past the edge
+    pub fn map_color_at(&self, point: BoardPoint) -> Option<[f32; 4]> {
+        let wall = self.tile_or_edge(point);
+        if wall.is_wall() {
+            return self.map_color(wall.id());
+        }
+        let point = self.wrap(point);
+        [BoardLayer::Objects, BoardLayer::Floor]
+            .iter()
+            .filter_map(|&layer| self.layer_at(layer, point))
+            .find(|&id| id != 0)
+            .and_then(|id| self.map_color(id))
+    }
+
+    /// Tile holding `point`, with `out_of_bounds` deciding what is past the edge
+    pub fn tile_or_edge(&self, point: BoardPoint) -> Tile {
+        match self.out_of_bounds {
+            OutOfBounds::Solid => self.tile_at(point).unwrap_or(Tile::EDGE),
+            OutOfBounds::Empty => self.tile_at(point).unwrap_or(Tile::EMPTY),
+            OutOfBounds::Wrap => self.tile_at(self.wrap(point)).unwrap_or(Tile::EDGE),
+        }
+    }
+
+    /// `point` moved onto the board under `OutOfBounds::Wrap`, unchanged otherwise
+    pub fn wrap(&self, point: BoardPoint) -> BoardPoint {
+        if self.out_of_bounds != OutOfBounds::Wrap {
+            return point;
+        }
+        BoardPoint {
+            x: point.x.rem_euclid(self.tiles_x as f64),
+            y: point.y.rem_euclid(self.tiles_y as f64),
+        }
     }
 
     pub fn get_tile(&self, board_index: usize) -> BoardPoint {
@@ -46,26 +370,64 @@ impl Board {
         }
     }
 
+    /// Whether a player can't stand at `point`
     pub fn is_wall_at(&self, point: BoardPoint) -> bool {
-        let board_index = self.get_index(point);
-        self.tiles[board_index] != 0
+        self.is_solid(self.tile_or_edge(point))
     }
 
-    pub fn draw(&self, transform: Matrix2d, graphics: &mut SharpGraphics) {
-        self.draw_grid(transform, graphics);
+    pub fn draw(&self, transform: Matrix2d, graphics: &mut SharpGraphics, grid: bool) {
+        self.draw_surfaces(transform, graphics, BoardLayer::Floor, 1.0);
+        if grid {
+            self.draw_grid(transform, graphics);
+        }
         self.draw_walls(transform, graphics);
+        // objects are drawn smaller, so the floor or wall under them shows
+        self.draw_surfaces(transform, graphics, BoardLayer::Objects, 0.5);
+    }
+
+    /// Draws the non-zero ids of `layer` as squares `scale` of a tile wide
+    fn draw_surfaces(
+        &self,
+        transform: Matrix2d,
+        graphics: &mut SharpGraphics,
+        layer: BoardLayer,
+        scale: f64,
+    ) {
+        let size = self.block_size * scale;
+        let inset = (self.block_size - size) / 2.0;
+        for (i, &id) in self.layer(layer).iter().enumerate() {
+            if id == 0 {
+                continue;
+            }
+            if let Some(color) = self.map_color(id) {
+                let point = self.get_tile(i);
+                graphics.draw_rectangle(
+                    color,
+                    [
+                        point.x * self.block_size + inset,
+                        point.y * self.block_size + inset,
+                        size,
+                        size,
+                    ],
+                    transform,
+                );
+            }
+        }
     }
 
     fn draw_walls(&self, transform: Matrix2d, graphics: &mut SharpGraphics) {
         for (i, &cell) in (0..).zip(self.tiles.iter()) {
-            let color = match cell {
-                1 => Some(colors::RED_ALPHA),
-                2 => Some(colors::BLUE_ALPHA),
-                3 => Some(colors::GREEN_ALPHA),
-                4 => Some(colors::ORANGE_ALPHA),
-                _ => None,
-            };
+            if let Some(frame @ TileFrame::Texture { .. }) = self.animation_frame(cell) {
+                let point = self.get_tile(i);
+                self.draw_texture(transform, graphics, point, frame);
+                continue;
+            }
 
+            let color = match self.map_color(cell) {
+                Some(color) => Some(color),
+                None if cell != 0 => Some(colors::UNKNOWN_TILE),
+                None => None,
+            };
             if let Some(color) = color {
                 let point = self.get_tile(i);
                 graphics.draw_rectangle(
@@ -82,6 +444,34 @@ impl Board {
         }
     }
 
+    /// Draws a textured tile as a coarse grid of texture samples
+    fn draw_texture(
+        &self,
+        transform: Matrix2d,
+        graphics: &mut SharpGraphics,
+        point: BoardPoint,
+        frame: &TileFrame,
+    ) {
+        const SAMPLES: u32 = 8;
+        let step = self.block_size / f64::from(SAMPLES);
+        for sample_y in 0..SAMPLES {
+            for sample_x in 0..SAMPLES {
+                let u = (f64::from(sample_x) + 0.5) / f64::from(SAMPLES);
+                let v = (f64::from(sample_y) + 0.5) / f64::from(SAMPLES);
+                graphics.draw_rectangle(
+                    frame.sample(u, v),
+                    [
+                        point.x * self.block_size + f64::from(sample_x) * step,
+                        point.y * self.block_size + f64::from(sample_y) * step,
+                        step,
+                        step,
+                    ],
+                    transform,
+                );
+            }
+        }
+    }
+
     fn draw_grid(&self, transform: Matrix2d, sharp_graphics_x: &mut SharpGraphics) {
         for i in 1..self.tiles_y {
             let offset = i as f64;
@@ -0,0 +1,223 @@
+use board::Board;
+use frame::Frame;
+use input::KeyBindings;
+use layout::Rect;
+use palette::{IndexedColors, IndexedFrame, PALETTE_SIZE};
+use player::{self, Player};
+use ray::Ray;
+use renderer::{self, RenderOptions};
+use stereo::{self, StereoConfig, StereoMode};
+
+/// One local view into the shared board
+pub struct Camera {
+    pub player: Player,
+    pub bindings: KeyBindings,
+    pub view: Rect,
+    /// left and right eye rays, empty unless stereo is on
+    pub eye_rays: [Vec<Ray>; 2],
+    pub frame: Frame,
+    /// right eye image while combining an anaglyph
+    scratch: Frame,
+    /// palette indices before they are resolved into `frame`
+    indexed: IndexedFrame,
+}
+
+impl Camera {
+    pub fn new(player: Player, bindings: KeyBindings) -> Camera {
+        Camera {
+            player,
+            bindings,
+            view: Rect::new(0.0, 0.0, 0.0, 0.0),
+            eye_rays: [Vec::new(), Vec::new()],
+            frame: Frame::new(1, 1),
+            scratch: Frame::new(1, 1),
+            indexed: IndexedFrame::new(1, 1),
+        }
+    }
+
+    pub fn set_view(&mut self, view: Rect, stereo_mode: StereoMode) {
+        let width = view.width.max(1.0) as usize;
+        self.view = view;
+        self.player.set_ray_count(width);
+        self.frame.resize(width as u32, view.height as u32);
+        self.indexed.resize(width as u32, view.height as u32);
+        let eye_widths = match stereo_mode {
+            StereoMode::Off => [0, 0],
+            StereoMode::SideBySide => [width / 2, width - width / 2],
+            StereoMode::Anaglyph => [width, width],
+        };
+        for (rays, &eye_width) in self.eye_rays.iter_mut().zip(eye_widths.iter()) {
+            rays.resize(eye_width, Ray::new());
+        }
+        if stereo_mode == StereoMode::Anaglyph {
+            self.scratch.resize(width as u32, view.height as u32);
+        }
+    }
+
+    pub fn update(&mut self, board: &Board, stereo: &StereoConfig) {
+        self.player.update(board);
+        if stereo.mode == StereoMode::Off {
+            return;
+        }
+        let eyes = stereo::eye_positions(&self.player, stereo.eye_distance, board);
+        // each eye sees as many radians per column as the whole view, so the
+        // halves of a side by side view aren't squashed
+        let width = self.player.rays.len().max(1) as f64;
+        for (&eye, rays) in eyes.iter().zip(self.eye_rays.iter_mut()) {
+            let fov = player::FOV * rays.len() as f64 / width;
+            self.player.cast_rays(eye, rays, fov, board);
+        }
+    }
+
+    /// Every ray cast by the last update, the player's and both eyes'
+    pub fn rays(&self) -> impl Iterator<Item = &Ray> {
+        self.player
+            .rays
+            .iter()
+            .chain(self.eye_rays[0].iter())
+            .chain(self.eye_rays[1].iter())
+    }
+
+    /// Renders the 3d view into `self.frame` and runs the post process chain over it
+    ///
+    /// Indexed views resolve through a palette the leading colour-only passes
+    /// already ran over, so those never touch the frame.
+    pub fn render(&mut self, board: &Board, options: &RenderOptions) {
+        let width = self.frame.width();
+        let mut first_pass = 0;
+        let resolved = options.indexed.map(|colors| {
+            let mut pixels = colors.palette.rgba();
+            first_pass = options.post.apply_to_palette(&mut pixels);
+            (colors, pixels)
+        });
+        let indexed = resolved.as_ref().map(|(colors, pixels)| (*colors, pixels));
+        match options.stereo_mode {
+            StereoMode::Off => {
+                let rays = &self.player.rays;
+                draw_rays(
+                    &mut self.frame,
+                    &mut self.indexed,
+                    0,
+                    width,
+                    rays,
+                    board,
+                    indexed,
+                );
+            }
+            StereoMode::SideBySide => {
+                let half = width / 2;
+                let [left, right] = &self.eye_rays;
+                draw_rays(
+                    &mut self.frame,
+                    &mut self.indexed,
+                    0,
+                    half,
+                    left,
+                    board,
+                    indexed,
+                );
+                draw_rays(
+                    &mut self.frame,
+                    &mut self.indexed,
+                    half,
+                    width - half,
+                    right,
+                    board,
+                    indexed,
+                );
+            }
+            StereoMode::Anaglyph => {
+                let [left, right] = &self.eye_rays;
+                draw_rays(
+                    &mut self.frame,
+                    &mut self.indexed,
+                    0,
+                    width,
+                    left,
+                    board,
+                    indexed,
+                );
+                draw_rays(
+                    &mut self.scratch,
+                    &mut self.indexed,
+                    0,
+                    width,
+                    right,
+                    board,
+                    indexed,
+                );
+                stereo::combine_anaglyph(&mut self.frame, &self.scratch);
+            }
+        }
+        options.post.apply_from(&mut self.frame, first_pass);
+    }
+}
+
+/// Renders straight into `frame`, or through `buffer` and the resolved palette
+/// `pixels` when drawing indexed
+fn draw_rays(
+    frame: &mut Frame,
+    buffer: &mut IndexedFrame,
+    left: u32,
+    width: u32,
+    rays: &[Ray],
+    board: &Board,
+    indexed: Option<(&IndexedColors, &[[u8; 4]; PALETTE_SIZE])>,
+) {
+    match indexed {
+        Some((colors, pixels)) => {
+            renderer::render_view_indexed(buffer, left, width, rays, board, colors);
+            buffer.resolve(pixels, frame, left, width);
+        }
+        None => renderer::render_view(frame, left, width, rays, board),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use map;
+    use map::picture::Legend;
+
+    /// Angle between the first and last ray of each eye
+    fn eye_spans(mode: StereoMode) -> Vec<f64> {
+        let map = map::load("assets/maps/default.txt", 50.0, &Legend::default()).unwrap();
+        let spawn = map.spawn(0);
+        let player = Player {
+            position: spawn.position,
+            angle: spawn.angle,
+            angle_tick: 0.0,
+            rays: Vec::new(),
+            move_step: 0.1,
+            parallel: false,
+            route: Vec::new(),
+        };
+        let mut camera = Camera::new(player, KeyBindings::for_player(0));
+        camera.set_view(Rect::new(0.0, 0.0, 401.0, 100.0), mode);
+        let stereo = StereoConfig {
+            mode,
+            eye_distance: 0.1,
+        };
+        camera.update(&map.board, &stereo);
+        camera
+            .eye_rays
+            .iter()
+            .map(|rays| rays[rays.len() - 1].angle - rays[0].angle)
+            .collect()
+    }
+
+    #[test]
+    fn side_by_side_eyes_see_half_the_field_of_view() {
+        // 401 columns split into 200 for the left eye and 201 for the right
+        let spans = eye_spans(StereoMode::SideBySide);
+        assert!((spans[0] - player::FOV * 200.0 / 401.0).abs() < 1e-9);
+        assert!((spans[1] - player::FOV * 201.0 / 401.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn anaglyph_eyes_see_the_whole_field_of_view() {
+        for span in eye_spans(StereoMode::Anaglyph) {
+            assert!((span - player::FOV).abs() < 1e-9);
+        }
+    }
+}
@@ -5,21 +5,10 @@ pub const ORANGE_ALPHA: [f32; 4] = [1.0, 0.6470588, 0.0, 0.90];
 pub const BLACK: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
 pub const _GREEN: [f32; 4] = [0.0, 1.0, 0.0, 1.0];
 pub const YELLOW: [f32; 4] = [1.0, 1.0, 0.6, 1.0];
-pub const GRAY_CEIL: [f32; 4] = [
-    0.2196078431372549,
-    0.2196078431372549,
-    0.2196078431372549,
-    1.0,
-];
-pub const GRAY_FLOOR: [f32; 4] = [
-    0.44313725490196076,
-    0.44313725490196076,
-    0.44313725490196076,
-    1.0,
-];
-pub const _BLUE_WALL: [f32; 4] = [
-    0.0,
-    0.0,
-    0.6039215686274509,
-    1.0,
-];
+pub const GRAY_CEIL: [f32; 4] = [0.21960784, 0.21960784, 0.21960784, 1.0];
+pub const GRAY_FLOOR: [f32; 4] = [0.44313726, 0.44313726, 0.44313726, 1.0];
+pub const GRAPH_FRAME: [f32; 4] = [0.6, 0.6, 0.6, 0.8];
+pub const SIGHT: [f32; 4] = [1.0, 0.9, 0.2, 0.35];
+pub const UNKNOWN_TILE: [f32; 4] = [1.0, 0.0, 1.0, 1.0];
+pub const HUD_PANEL: [f32; 4] = [1.0, 1.0, 1.0, 0.75];
+pub const _BLUE_WALL: [f32; 4] = [0.0, 0.0, 0.6039216, 1.0];
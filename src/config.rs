@@ -0,0 +1,244 @@
+use board::OutOfBounds;
+use hud::Anchor;
+use layout::{LayoutConfig, LayoutMode};
+use map::generate::Generator;
+use path::Movement;
+use postfx::PostChain;
+use stereo::{StereoConfig, StereoMode};
+
+/// Start up settings, read from the command line
+///
+/// `ray-casting [--map FILE.txt|.json|.ron|.tmj|.tmx|.png] [--map-legend FILE]
+/// [--generate maze|dungeon|caves] [--seed N] [--map-size WxH] [--tiles FILE.ron]
+/// [--out-of-bounds solid|empty|wrap] [--path 4|8] [--check-map] [--save-map OUT.json|.ron]
+/// [--edit-save OUT.json|.ron] [--window WxH] [--layout split|full] [--threads N]
+/// [--single-threaded]
+/// [--players 1-4] [--stereo off|side|anaglyph] [--eye-distance TILES] [--post PASSES]
+/// [--palette FILE.pal] [--hud-font FILE.ttf] [--hud-font-size N]
+/// [--hud-anchor top-left|top-right|bottom-left|bottom-right] [--headless OUT.png]
+/// [--fixed-compare]`
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// map file to play, text, document or Tiled, the bundled default map when `None`
+    pub map: Option<String>,
+    /// generate the map instead of loading one
+    pub generate: Option<Generator>,
+    /// seed for `generate`, picked from the clock and printed when `None`
+    pub seed: Option<u64>,
+    /// generated board size in tiles, 32x32 when `None`
+    pub map_size: Option<[u32; 2]>,
+    /// colours of a `.png` map, added to the built-in legend
+    pub map_legend: Option<String>,
+    /// where the map editor saves when set, otherwise `NAME.edited.ron` next to map
+    /// `NAME`, or `GENERATOR-SEED.ron` for a generated map
+    pub edit_save: Option<String>,
+    /// tile kinds, the bundled registry when `None`
+    pub tiles: Option<String>,
+    /// what players, rays and the mini map find past the edge of the board
+    pub out_of_bounds: OutOfBounds,
+    /// how click to move routes step between tiles
+    pub path_movement: Movement,
+    /// validate the loaded map and exit, non-zero when it has problems
+    pub check_map: bool,
+    /// write the loaded map as a map document and exit instead of opening a window
+    pub save_map: Option<String>,
+    pub window_size: [u32; 2],
+    pub layout: LayoutConfig,
+    /// cast rays on a thread pool
+    pub parallel: bool,
+    /// thread pool size, `None` lets rayon pick one thread per core
+    pub threads: Option<usize>,
+    /// split screen cameras sharing the board
+    pub players: usize,
+    pub stereo: StereoConfig,
+    pub post: PostChain,
+    /// render one frame of the 3d view to this png instead of opening a window
+    pub headless: Option<String>,
+    /// JASC or raw 768 byte palette, switches on indexed rendering
+    pub palette: Option<String>,
+    /// font for the hud text, the bundled Fira Sans when `None`
+    pub hud_font: Option<String>,
+    pub hud_font_size: u32,
+    /// corner of the hud area the debug text is pinned to
+    pub hud_anchor: Anchor,
+    /// check the fixed point core against `ray.rs` and exit instead of opening a window
+    pub fixed_compare: bool,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            map: None,
+            generate: None,
+            seed: None,
+            map_size: None,
+            map_legend: None,
+            check_map: false,
+            edit_save: None,
+            tiles: None,
+            out_of_bounds: OutOfBounds::Solid,
+            path_movement: Movement::Eight,
+            save_map: None,
+            window_size: [1100, 1100],
+            layout: LayoutConfig::default(),
+            parallel: true,
+            threads: None,
+            players: 1,
+            stereo: StereoConfig::default(),
+            post: PostChain::default(),
+            headless: None,
+            palette: None,
+            hud_font: None,
+            hud_font_size: 15,
+            hud_anchor: Anchor::TopLeft,
+            fixed_compare: false,
+        }
+    }
+}
+
+impl Config {
+    pub fn from_args<I: Iterator<Item = String>>(mut args: I) -> Result<Config, String> {
+        let mut config = Config::default();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--map" => {
+                    config.map = Some(next_value(&mut args, &arg)?);
+                }
+                "--generate" => {
+                    let value = next_value(&mut args, &arg)?;
+                    config.generate = Some(
+                        Generator::parse(&value)
+                            .ok_or_else(|| format!("unknown generator '{}'", value))?,
+                    );
+                }
+                "--seed" => {
+                    let value = next_value(&mut args, &arg)?;
+                    config.seed = Some(
+                        value
+                            .parse::<u64>()
+                            .map_err(|_| format!("bad seed '{}'", value))?,
+                    );
+                }
+                "--map-size" => {
+                    let value = next_value(&mut args, &arg)?;
+                    config.map_size = Some(parse_size(&value)?);
+                }
+                "--map-legend" => {
+                    config.map_legend = Some(next_value(&mut args, &arg)?);
+                }
+                "--edit-save" => {
+                    config.edit_save = Some(next_value(&mut args, &arg)?);
+                }
+                "--tiles" => {
+                    config.tiles = Some(next_value(&mut args, &arg)?);
+                }
+                "--out-of-bounds" => {
+                    let value = next_value(&mut args, &arg)?;
+                    config.out_of_bounds = OutOfBounds::parse(&value)
+                        .ok_or_else(|| format!("unknown out of bounds policy '{}'", value))?;
+                }
+                "--path" => {
+                    let value = next_value(&mut args, &arg)?;
+                    config.path_movement = Movement::parse(&value)
+                        .ok_or_else(|| format!("bad path movement '{}', expected 4 or 8", value))?;
+                }
+                "--check-map" => {
+                    config.check_map = true;
+                }
+                "--save-map" => {
+                    config.save_map = Some(next_value(&mut args, &arg)?);
+                }
+                "--window" => {
+                    let value = next_value(&mut args, &arg)?;
+                    config.window_size = parse_size(&value)?;
+                }
+                "--layout" => {
+                    let value = next_value(&mut args, &arg)?;
+                    config.layout.mode = LayoutMode::parse(&value)
+                        .ok_or_else(|| format!("unknown layout '{}'", value))?;
+                }
+                "--threads" => {
+                    let value = next_value(&mut args, &arg)?;
+                    match value.parse::<usize>() {
+                        Ok(threads) if threads > 0 => config.threads = Some(threads),
+                        _ => return Err(format!("bad thread count '{}'", value)),
+                    }
+                }
+                "--players" => {
+                    let value = next_value(&mut args, &arg)?;
+                    match value.parse::<usize>() {
+                        Ok(players) if (1..=4).contains(&players) => config.players = players,
+                        _ => return Err(format!("bad player count '{}', expected 1-4", value)),
+                    }
+                }
+                "--stereo" => {
+                    let value = next_value(&mut args, &arg)?;
+                    config.stereo.mode = StereoMode::parse(&value)
+                        .ok_or_else(|| format!("unknown stereo mode '{}'", value))?;
+                }
+                "--eye-distance" => {
+                    let value = next_value(&mut args, &arg)?;
+                    match value.parse::<f64>() {
+                        Ok(distance) if distance >= 0.0 => config.stereo.eye_distance = distance,
+                        _ => return Err(format!("bad eye distance '{}'", value)),
+                    }
+                }
+                "--post" => {
+                    let value = next_value(&mut args, &arg)?;
+                    config.post = PostChain::parse(&value)?;
+                }
+                "--headless" => {
+                    config.headless = Some(next_value(&mut args, &arg)?);
+                }
+                "--palette" => {
+                    config.palette = Some(next_value(&mut args, &arg)?);
+                }
+                "--hud-font" => {
+                    config.hud_font = Some(next_value(&mut args, &arg)?);
+                }
+                "--hud-font-size" => {
+                    let value = next_value(&mut args, &arg)?;
+                    match value.parse::<u32>() {
+                        Ok(size) if size > 0 => config.hud_font_size = size,
+                        _ => return Err(format!("bad font size '{}'", value)),
+                    }
+                }
+                "--hud-anchor" => {
+                    let value = next_value(&mut args, &arg)?;
+                    config.hud_anchor = Anchor::parse(&value)
+                        .ok_or_else(|| format!("unknown hud anchor '{}'", value))?;
+                }
+                "--single-threaded" => {
+                    config.parallel = false;
+                }
+                "--fixed-compare" => {
+                    config.fixed_compare = true;
+                }
+                _ => return Err(format!("unknown argument '{}'", arg)),
+            }
+        }
+        if config.generate.is_some() && config.map.is_some() {
+            return Err("--generate and --map can't be used together".to_string());
+        }
+        if config.seed.is_some() && config.generate.is_none() {
+            return Err("--seed needs --generate".to_string());
+        }
+        if config.map_size.is_some() && config.generate.is_none() {
+            return Err("--map-size needs --generate".to_string());
+        }
+        Ok(config)
+    }
+}
+
+fn next_value<I: Iterator<Item = String>>(args: &mut I, name: &str) -> Result<String, String> {
+    args.next()
+        .ok_or_else(|| format!("missing value for '{}'", name))
+}
+
+fn parse_size(value: &str) -> Result<[u32; 2], String> {
+    let mut parts = value.split('x').map(|part| part.parse::<u32>());
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(Ok(width)), Some(Ok(height)), None) if width > 0 && height > 0 => Ok([width, height]),
+        _ => Err(format!("bad size '{}', expected WIDTHxHEIGHT", value)),
+    }
+}
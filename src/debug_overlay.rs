@@ -0,0 +1,133 @@
+use piston::input::Key;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Layer {
+    /// tile grid lines on the 2d map
+    Grid,
+    /// line from the player to every wall hit
+    RayFan,
+    /// every grid crossing tested by every ray
+    Intercepts,
+    /// the wall intersection of every ray
+    HitPoints,
+    /// the tile the player is in and the reach of one move step
+    CollisionBox,
+    /// frame time and frame rate text
+    FpsText,
+    /// mouse position and the board tile under it
+    MouseProbe,
+    /// per frame timings and the frame time graph
+    Stats,
+    /// tiles the player can see and the outline of what they see
+    Sight,
+}
+
+impl Layer {
+    pub const ALL: [Layer; 9] = [
+        Layer::Grid,
+        Layer::RayFan,
+        Layer::Intercepts,
+        Layer::HitPoints,
+        Layer::CollisionBox,
+        Layer::FpsText,
+        Layer::MouseProbe,
+        Layer::Stats,
+        Layer::Sight,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Layer::Grid => "grid",
+            Layer::RayFan => "rays",
+            Layer::Intercepts => "intercepts",
+            Layer::HitPoints => "hits",
+            Layer::CollisionBox => "collision",
+            Layer::FpsText => "fps",
+            Layer::MouseProbe => "mouse",
+            Layer::Stats => "stats",
+            Layer::Sight => "sight",
+        }
+    }
+
+    pub fn hotkey(self) -> Key {
+        match self {
+            Layer::Grid => Key::F1,
+            Layer::RayFan => Key::F2,
+            Layer::Intercepts => Key::F3,
+            Layer::HitPoints => Key::F4,
+            Layer::CollisionBox => Key::F5,
+            Layer::FpsText => Key::F6,
+            Layer::MouseProbe => Key::F7,
+            Layer::Stats => Key::F8,
+            Layer::Sight => Key::F10,
+        }
+    }
+
+    pub fn from_hotkey(key: Key) -> Option<Layer> {
+        Layer::ALL
+            .iter()
+            .cloned()
+            .find(|layer| layer.hotkey() == key)
+    }
+
+    fn bit(self) -> u32 {
+        1 << Layer::ALL
+            .iter()
+            .position(|&layer| layer == self)
+            .expect("layer in ALL")
+    }
+}
+
+/// Which debug layers are drawn
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DebugLayers {
+    enabled: u32,
+}
+
+impl Default for DebugLayers {
+    fn default() -> DebugLayers {
+        let mut layers = DebugLayers { enabled: 0 };
+        for &layer in [
+            Layer::Grid,
+            Layer::RayFan,
+            Layer::HitPoints,
+            Layer::FpsText,
+            Layer::MouseProbe,
+        ]
+        .iter()
+        {
+            layers.set(layer, true);
+        }
+        layers
+    }
+}
+
+impl DebugLayers {
+    pub fn is_on(&self, layer: Layer) -> bool {
+        self.enabled & layer.bit() != 0
+    }
+
+    pub fn set(&mut self, layer: Layer, on: bool) {
+        if on {
+            self.enabled |= layer.bit();
+        } else {
+            self.enabled &= !layer.bit();
+        }
+    }
+
+    pub fn toggle(&mut self, layer: Layer) {
+        let on = self.is_on(layer);
+        self.set(layer, !on);
+    }
+}
+
+impl std::fmt::Display for DebugLayers {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Debug {{ ")?;
+        for &layer in Layer::ALL.iter() {
+            let state = if self.is_on(layer) { "on" } else { "off" };
+            write!(f, "{:?} {}: {}, ", layer.hotkey(), layer.name(), state)?;
+        }
+        write!(f, "}}")
+    }
+}
@@ -0,0 +1,338 @@
+use image::{Rgba, RgbaImage};
+use point::{InterceptType, RayPoint};
+use ray::Ray;
+
+/// Side of the generated decal images, in texels
+const DECAL_SIZE: u32 = 16;
+
+/// Side of a wall tile a decal sits on, named by the direction it faces
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Face {
+    North,
+    South,
+    East,
+    West,
+}
+
+impl Face {
+    /// Face a ray heading along `angle` meets where it crosses a grid line
+    pub fn seen_along(intercept_type: InterceptType, angle: f64) -> Face {
+        let (sin, cos) = angle.sin_cos();
+        match intercept_type {
+            // horizontal grid line, so the north or south face
+            InterceptType::XIntercept if sin > 0.0 => Face::North,
+            InterceptType::XIntercept => Face::South,
+            InterceptType::YIntercept if cos > 0.0 => Face::West,
+            InterceptType::YIntercept => Face::East,
+        }
+    }
+
+    /// How far along the face `point` lies, in `0.0..=1.0` from left to right
+    /// as seen by someone facing it
+    pub fn offset(self, point: &RayPoint, block_size: f64) -> f64 {
+        let along = match self {
+            Face::North | Face::South => point.x,
+            Face::East | Face::West => point.y,
+        };
+        let offset = (along / block_size).rem_euclid(1.0);
+        // facing a north face looks towards +y, so +x is on the left, and
+        // facing an east face looks towards -x, so +y is on the left
+        match self {
+            Face::North | Face::East => 1.0 - offset,
+            Face::South | Face::West => offset,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DecalKind {
+    Sign,
+    BulletHole,
+    Poster,
+    Scorch,
+}
+
+impl DecalKind {
+    pub const ALL: [DecalKind; 4] = [
+        DecalKind::Sign,
+        DecalKind::BulletHole,
+        DecalKind::Poster,
+        DecalKind::Scorch,
+    ];
+
+    pub fn next(self) -> DecalKind {
+        let i = DecalKind::ALL
+            .iter()
+            .position(|&kind| kind == self)
+            .expect("kind in ALL");
+        DecalKind::ALL[(i + 1) % DecalKind::ALL.len()]
+    }
+
+    /// Width and height as fractions of the tile width and wall height
+    pub fn size(self) -> (f64, f64) {
+        match self {
+            DecalKind::Sign => (0.5, 0.3),
+            DecalKind::BulletHole => (0.1, 0.1),
+            DecalKind::Poster => (0.4, 0.6),
+            DecalKind::Scorch => (0.6, 0.6),
+        }
+    }
+
+    fn image(self) -> RgbaImage {
+        let center = (DECAL_SIZE as f32 - 1.0) / 2.0;
+        let radius = |x: u32, y: u32| {
+            let (dx, dy) = (x as f32 - center, y as f32 - center);
+            (dx * dx + dy * dy).sqrt() / center
+        };
+        let last = DECAL_SIZE - 1;
+        RgbaImage::from_fn(DECAL_SIZE, DECAL_SIZE, |x, y| match self {
+            DecalKind::Sign => {
+                let border = x < 2 || y < 2 || x > last - 2 || y > last - 2;
+                // a right pointing arrow
+                let shaft = (y == 7 || y == 8) && (4..9).contains(&x);
+                let head =
+                    (9..=12).contains(&x) && (2 * y as i32 - 15).abs() <= 2 * (12 - x as i32) + 1;
+                if border || shaft || head {
+                    Rgba([180, 20, 20, 255])
+                } else {
+                    Rgba([235, 235, 225, 255])
+                }
+            }
+            DecalKind::BulletHole => match radius(x, y) {
+                r if r < 0.45 => Rgba([15, 15, 15, 255]),
+                r if r < 0.8 => Rgba([90, 90, 90, 200]),
+                _ => Rgba([0, 0, 0, 0]),
+            },
+            DecalKind::Poster => {
+                if x == 0 || y == 0 || x == last || y == last {
+                    Rgba([40, 40, 40, 255])
+                } else if y < 6 {
+                    Rgba([230, 190, 60, 255])
+                } else if y % 3 == 0 && x > 2 && x < last - 2 {
+                    Rgba([60, 60, 60, 255])
+                } else {
+                    Rgba([245, 240, 220, 255])
+                }
+            }
+            DecalKind::Scorch => {
+                let alpha = (1.0 - radius(x, y)).clamp(0.0, 1.0) * 230.0;
+                Rgba([10, 8, 5, alpha as u8])
+            }
+        })
+    }
+}
+
+pub struct Decal {
+    pub board_index: usize,
+    pub face: Face,
+    /// centre of the decal along the face, `0.0..1.0` from left to right as
+    /// seen facing it
+    pub offset: f64,
+    /// centre of the decal down the wall, 0 at the top and 1 at the bottom
+    pub height_offset: f64,
+    pub kind: DecalKind,
+}
+
+/// Where a ray met a wall: the tile, the face it hit and how far along the face
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WallHit {
+    pub board_index: usize,
+    pub face: Face,
+    pub offset: f64,
+}
+
+impl WallHit {
+    pub fn from_ray(ray: &Ray, block_size: f64) -> Option<WallHit> {
+        let point = ray.wall_intersection?;
+        let board_index = point.board_index?;
+        let face = Face::seen_along(point.intercept_type, ray.angle);
+        Some(WallHit {
+            board_index,
+            face,
+            offset: face.offset(&point, block_size),
+        })
+    }
+}
+
+/// Decals stuck to wall faces, drawn over the walls by the 3d renderer
+pub struct Decals {
+    decals: Vec<Decal>,
+    /// one image per `DecalKind::ALL` entry
+    images: Vec<RgbaImage>,
+}
+
+impl Decals {
+    pub fn new() -> Decals {
+        Decals {
+            decals: Vec::new(),
+            images: DecalKind::ALL.iter().map(|kind| kind.image()).collect(),
+        }
+    }
+
+    pub fn add(&mut self, decal: Decal) {
+        self.decals.push(decal);
+    }
+
+    /// Sticks a `kind` decal where `ray` hit a wall, at eye height, returning
+    /// false when the ray hit nothing
+    pub fn place_at_hit(&mut self, ray: &Ray, block_size: f64, kind: DecalKind) -> bool {
+        match WallHit::from_ray(ray, block_size) {
+            Some(hit) => {
+                self.add(Decal {
+                    board_index: hit.board_index,
+                    face: hit.face,
+                    offset: hit.offset,
+                    height_offset: 0.5,
+                    kind,
+                });
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.decals.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.decals.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.decals.is_empty()
+    }
+
+    /// Decals crossing the wall column that shows `hit`
+    pub fn column(&self, hit: &WallHit) -> DecalColumn<'_> {
+        let layers = self
+            .decals
+            .iter()
+            .filter(|decal| decal.board_index == hit.board_index && decal.face == hit.face)
+            .filter_map(|decal| {
+                let (width, height) = decal.kind.size();
+                let u = (hit.offset - decal.offset) / width + 0.5;
+                if !(0.0..1.0).contains(&u) {
+                    return None;
+                }
+                let image = &self.images[DecalKind::ALL
+                    .iter()
+                    .position(|&kind| kind == decal.kind)
+                    .expect("kind in ALL")];
+                let x = (u * f64::from(image.width())) as u32;
+                Some(DecalLayer {
+                    image,
+                    x,
+                    top: decal.height_offset - height / 2.0,
+                    height,
+                })
+            })
+            .collect();
+        DecalColumn { layers }
+    }
+}
+
+/// One column of one decal image, placed down the wall
+struct DecalLayer<'a> {
+    image: &'a RgbaImage,
+    x: u32,
+    top: f64,
+    height: f64,
+}
+
+pub struct DecalColumn<'a> {
+    layers: Vec<DecalLayer<'a>>,
+}
+
+impl<'a> DecalColumn<'a> {
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+
+    /// Colour the decals put at `v` down the wall, 0 at the top and 1 at the
+    /// bottom, later decals are drawn over earlier ones
+    pub fn sample(&self, v: f64) -> Option<[f32; 4]> {
+        let mut color: Option<[f32; 4]> = None;
+        for layer in self.layers.iter() {
+            let v = (v - layer.top) / layer.height;
+            if !(0.0..1.0).contains(&v) {
+                continue;
+            }
+            let y = (v * f64::from(layer.image.height())) as u32;
+            let texel = layer.image.get_pixel(layer.x, y).0;
+            if texel[3] == 0 {
+                continue;
+            }
+            let over = [
+                f32::from(texel[0]) / 255.0,
+                f32::from(texel[1]) / 255.0,
+                f32::from(texel[2]) / 255.0,
+                f32::from(texel[3]) / 255.0,
+            ];
+            color = Some(match color {
+                Some(under) => blend(under, over),
+                None => over,
+            });
+        }
+        color
+    }
+}
+
+/// `over` composited onto `under`, neither premultiplied
+fn blend(under: [f32; 4], over: [f32; 4]) -> [f32; 4] {
+    let under_weight = under[3] * (1.0 - over[3]);
+    let alpha = over[3] + under_weight;
+    let mix = |i: usize| (over[i] * over[3] + under[i] * under_weight) / alpha;
+    [mix(0), mix(1), mix(2), alpha]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use board::Board;
+    use point::BoardPoint;
+    use std::f64::consts::{FRAC_PI_2, PI};
+
+    /// Texel columns of a sign on the pillar in the middle of a 5x5 room, for
+    /// rays swept from left to right across it from `eye` looking along `angle`
+    fn sign_columns(eye: BoardPoint, angle: f64, face: Face) -> Vec<u32> {
+        let mut tiles = vec![0; 25];
+        tiles[12] = 1;
+        let board = Board::new(tiles, 5, 5, 50.0);
+        let mut decals = Decals::new();
+        let mut ray = Ray::new();
+        ray.update(eye, angle, &board);
+        assert!(decals.place_at_hit(&ray, board.block_size, DecalKind::Sign));
+        // screen columns run towards larger angles, see `Player::cast_rays`
+        (-5..=5)
+            .map(|step| {
+                ray.update(eye, angle + f64::from(step) * 0.02, &board);
+                let hit = WallHit::from_ray(&ray, board.block_size).unwrap();
+                assert_eq!((hit.board_index, hit.face), (12, face));
+                let column = decals.column(&hit);
+                assert_eq!(column.layers.len(), 1);
+                column.layers[0].x
+            })
+            .collect()
+    }
+
+    #[test]
+    fn decals_read_left_to_right_on_every_face() {
+        let views = [
+            (BoardPoint { x: 2.5, y: 0.5 }, FRAC_PI_2, Face::North),
+            (BoardPoint { x: 4.5, y: 2.5 }, PI, Face::East),
+            (BoardPoint { x: 2.5, y: 4.5 }, -FRAC_PI_2, Face::South),
+            (BoardPoint { x: 0.5, y: 2.5 }, 0.0, Face::West),
+        ];
+        for &(eye, angle, face) in views.iter() {
+            let columns = sign_columns(eye, angle, face);
+            assert!(
+                columns.windows(2).all(|pair| pair[0] <= pair[1]),
+                "{:?} face drew columns {:?}",
+                face,
+                columns
+            );
+            assert!(columns[0] < columns[columns.len() - 1]);
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use board::{Board, BoardLayer, Tile};
+use graphics::math::Matrix2d;
+use map::document::MapDocument;
+use map::{self, Entity, Spawn};
+use piston::input::Key;
+use point::{BoardPoint, ScreenPoint};
+use sharp_graphics::SharpGraphics;
+
+/// Distance between painted points along a drag, in tiles, so fast strokes
+/// leave no gaps
+const STROKE_STEP: f64 = 0.25;
+
+/// Paints tiles onto one layer of the board from the top-down map
+///
+/// The 3d view casts its rays against the same board every update, so edits
+/// show there straight away. The spawns and entities of the loaded map are kept
+/// so a saved map is complete.
+pub struct Editor {
+    pub enabled: bool,
+    /// tile id painted by the left mouse button
+    pub tile: u32,
+    /// layer painted on
+    pub layer: BoardLayer,
+    /// tile being painted and the last map point painted, while a button is held
+    stroke: Option<(Tile, BoardPoint)>,
+    pub save_path: String,
+    spawns: Vec<Spawn>,
+    entities: Vec<Entity>,
+    /// result of the last save
+    status: String,
+}
+
+impl Editor {
+    pub fn new(save_path: String, spawns: Vec<Spawn>, entities: Vec<Entity>) -> Editor {
+        Editor {
+            enabled: false,
+            tile: 1,
+            layer: BoardLayer::Walls,
+            stroke: None,
+            save_path,
+            spawns,
+            entities,
+            status: String::new(),
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+        self.stroke = None;
+    }
+
+    /// Number keys 1 to 9 pick those tile ids and 0 picks 10, minus and equals
+    /// step through the ids `board` knows, Page Down moves on to the next layer.
+    /// Ids with no colour are never picked. Returns whether `key` was one of them
+    pub fn select(&mut self, key: Key, board: &Board) -> bool {
+        const KEYS: [Key; 10] = [
+            Key::D1,
+            Key::D2,
+            Key::D3,
+            Key::D4,
+            Key::D5,
+            Key::D6,
+            Key::D7,
+            Key::D8,
+            Key::D9,
+            Key::D0,
+        ];
+        let known = board.known_tiles();
+        let tile = match key {
+            Key::Minus => known.iter().rev().find(|&&id| id < self.tile).cloned(),
+            Key::Equals => known.iter().find(|&&id| id > self.tile).cloned(),
+            Key::PageDown => {
+                self.layer = self.layer.next();
+                None
+            }
+            _ => match KEYS.iter().position(|&k| k == key) {
+                Some(i) => Some(i as u32 + 1).filter(|id| known.contains(id)),
+                None => return false,
+            },
+        };
+        if let Some(tile) = tile {
+            self.tile = tile;
+        }
+        true
+    }
+
+    /// Starts painting `tile` at `point`, in board units
+    pub fn start_stroke(&mut self, board: &mut Board, tile: Tile, point: BoardPoint) {
+        self.stroke = Some((tile, point));
+        board.set_tile(self.layer, point, tile);
+    }
+
+    /// Paints every tile between the last painted point and `point`
+    pub fn continue_stroke(&mut self, board: &mut Board, point: BoardPoint) {
+        let (tile, from) = match self.stroke {
+            Some(stroke) => stroke,
+            None => return,
+        };
+        let distance = (point.x - from.x).hypot(point.y - from.y);
+        let steps = (distance / STROKE_STEP).ceil().max(1.0) as usize;
+        for step in 1..=steps {
+            let t = step as f64 / steps as f64;
+            board.set_tile(
+                self.layer,
+                BoardPoint {
+                    x: from.x + (point.x - from.x) * t,
+                    y: from.y + (point.y - from.y) * t,
+                },
+                tile,
+            );
+        }
+        self.stroke = Some((tile, point));
+    }
+
+    pub fn end_stroke(&mut self) {
+        self.stroke = None;
+    }
+
+    pub fn save(&mut self, board: &Board) {
+        let document = MapDocument::from_parts(board, &self.spawns, &self.entities);
+        self.status = match map::save_document(&document, &self.save_path) {
+            Ok(()) => format!("saved {}", self.save_path),
+            Err(message) => message,
+        };
+    }
+
+    /// Shades the tile under `cursor`, a map space point, in the selected tile's colour
+    pub fn draw_cursor(
+        &self,
+        transform: Matrix2d,
+        graphics: &mut SharpGraphics,
+        board: &Board,
+        cursor: ScreenPoint,
+    ) {
+        let point = board.point_from(cursor);
+        if board.tile_at(point).is_none() {
+            return;
+        }
+        let [r, g, b, _] = board.map_color(self.tile).unwrap_or([0.5, 0.5, 0.5, 1.0]);
+        graphics.draw_rectangle(
+            [r, g, b, 0.5],
+            [
+                point.x.floor() * board.block_size,
+                point.y.floor() * board.block_size,
+                board.block_size,
+                board.block_size,
+            ],
+            transform,
+        );
+    }
+
+    /// Hud line naming the selected tile, `None` outside edit mode
+    pub fn describe(&self, board: &Board) -> Option<String> {
+        if !self.enabled {
+            return None;
+        }
+        let name = board
+            .registry
+            .get(self.tile)
+            .map_or("unknown", |kind| kind.name.as_str());
+        Some(format!(
+            "Edit {{ {}: {} ({}), F9 saves to {} }} {}",
+            self.layer.name(),
+            self.tile,
+            name,
+            self.save_path,
+            self.status
+        ))
+    }
+}
@@ -0,0 +1,173 @@
+use board::{Board, OutOfBounds, Tile};
+use point::{BoardPoint, InterceptType};
+use ray::Ray;
+use ray_casting_fixed::angle::FULL_TURN;
+use ray_casting_fixed::fixed::{FRAC_BITS, ONE};
+use ray_casting_fixed::{cast_ray, Angle, Fixed, FixedBoard, FixedHit, Side};
+
+/// Largest distance difference, in tiles, still counted as the same hit
+const TOLERANCE: f64 = 0.02;
+/// Offsets inside each open tile that rays are cast from
+const SAMPLE_OFFSETS: [(f64, f64); 4] = [(0.5, 0.5), (0.25, 0.75), (0.8, 0.1), (0.1, 0.9)];
+
+pub struct Mismatch {
+    pub position: BoardPoint,
+    pub angle: Angle,
+    pub float_hit: Option<(usize, f64)>,
+    pub fixed_hit: Option<(usize, f64)>,
+}
+
+impl std::fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "pos: {}, angle: {}, f64: {:?}, fixed: {:?}",
+            self.position,
+            self.angle.steps(),
+            self.float_hit,
+            self.fixed_hit
+        )
+    }
+}
+
+pub struct CompareReport {
+    pub rays: usize,
+    pub mismatches: Vec<Mismatch>,
+}
+
+/// Casts the same rays through `ray.rs` and the fixed point core and collects every
+/// ray where they disagree on the wall tile or the distance to it
+///
+/// Positions and angles are snapped to the fixed point grid first so both sides
+/// start from exactly the same ray.
+///
+/// The fixed core stops at every nonzero tile and ends rays at the board edge.
+/// Tiles rays see past, like glass, are handed to it as empty floor. Rays
+/// starting on the board never come back once they leave, so `Solid` and
+/// `Empty` edges look the same to it, but it has no way to wrap rays.
+pub fn compare(board: &Board, angle_step: u16) -> Result<CompareReport, String> {
+    if board.out_of_bounds == OutOfBounds::Wrap {
+        return Err("the fixed point core can't wrap rays around the board".to_string());
+    }
+    let sight_tiles = board
+        .tiles
+        .iter()
+        .map(|&id| if board.blocks_sight(Tile(id)) { id } else { 0 })
+        .collect::<Vec<u32>>();
+    let fixed_board = FixedBoard::new(&sight_tiles, board.tiles_x, board.tiles_y)
+        .expect("board size doesn't match its tiles");
+    let mut report = CompareReport {
+        rays: 0,
+        mismatches: Vec::new(),
+    };
+    let mut ray = Ray::new();
+
+    for (board_index, _) in board.tiles.iter().enumerate().filter(|(_, &t)| t == 0) {
+        let tile = board.get_tile(board_index);
+        for &(offset_x, offset_y) in SAMPLE_OFFSETS.iter() {
+            let x = to_fixed(tile.x + offset_x);
+            let y = to_fixed(tile.y + offset_y);
+            let position = BoardPoint {
+                x: from_fixed(x),
+                y: from_fixed(y),
+            };
+            for steps in (0..FULL_TURN).step_by(angle_step.max(1) as usize) {
+                let angle = Angle::new(steps);
+                ray.update(position, to_radians(angle), board);
+                let fixed_hit = cast_ray(&fixed_board, x, y, angle);
+                report.rays += 1;
+                if !hits_match(board, &ray, fixed_hit) {
+                    report.mismatches.push(Mismatch {
+                        position,
+                        angle,
+                        float_hit: float_summary(board, &ray),
+                        fixed_hit: fixed_hit.map(|hit| (hit.board_index, from_fixed(hit.distance))),
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+fn hits_match(board: &Board, ray: &Ray, fixed_hit: Option<FixedHit>) -> bool {
+    let (float_point, fixed_hit) = match (ray.wall_intersection, fixed_hit) {
+        (None, None) => return true,
+        (Some(point), Some(hit)) => (point, hit),
+        _ => return false,
+    };
+    let float_distance = ray.wall_distance / board.block_size;
+    if (float_distance - from_fixed(fixed_hit.distance)).abs() > TOLERANCE {
+        return false;
+    }
+    let same_side = match fixed_hit.side {
+        Side::Horizontal => float_point.intercept_type == InterceptType::XIntercept,
+        Side::Vertical => float_point.intercept_type == InterceptType::YIntercept,
+    };
+    // rays through a grid corner may pick either neighbour, which is fine as long
+    // as both land on the same spot
+    (same_side && float_point.board_index == Some(fixed_hit.board_index))
+        || point_distance(board, float_point.x, float_point.y, fixed_hit) <= TOLERANCE
+}
+
+fn point_distance(board: &Board, x: f64, y: f64, fixed_hit: FixedHit) -> f64 {
+    let dx = x / board.block_size - from_fixed(fixed_hit.x);
+    let dy = y / board.block_size - from_fixed(fixed_hit.y);
+    (dx * dx + dy * dy).sqrt()
+}
+
+fn float_summary(board: &Board, ray: &Ray) -> Option<(usize, f64)> {
+    let point = ray.wall_intersection?;
+    Some((point.board_index?, ray.wall_distance / board.block_size))
+}
+
+fn to_fixed(value: f64) -> Fixed {
+    (value * f64::from(ONE)).round() as Fixed
+}
+
+fn from_fixed(value: Fixed) -> f64 {
+    f64::from(value) / f64::from(1 << FRAC_BITS)
+}
+
+fn to_radians(angle: Angle) -> f64 {
+    f64::from(angle.steps()) * std::f64::consts::PI * 2.0 / f64::from(FULL_TURN)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use map;
+    use map::picture::Legend;
+    use tiles::TileRegistry;
+
+    fn hall() -> Board {
+        let mut board = map::load("assets/maps/hall.tmj", 50.0, &Legend::default())
+            .unwrap()
+            .board;
+        board.registry = TileRegistry::load("assets/tiles.ron").unwrap();
+        board
+    }
+
+    #[test]
+    fn see_through_tiles_match() {
+        let board = hall();
+        assert!(board
+            .tiles
+            .iter()
+            .any(|&id| id != 0 && !board.blocks_sight(Tile(id))));
+        let report = compare(&board, 16).unwrap();
+        assert!(report.rays > 0);
+        let mismatches: Vec<String> = report.mismatches.iter().map(|m| m.to_string()).collect();
+        assert_eq!(mismatches, Vec::<String>::new());
+    }
+
+    #[test]
+    fn open_edges_match_and_wrapping_is_refused() {
+        let mut board = hall();
+        board.out_of_bounds = OutOfBounds::Empty;
+        assert!(compare(&board, 64).unwrap().mismatches.is_empty());
+        board.out_of_bounds = OutOfBounds::Wrap;
+        assert!(compare(&board, 64).is_err());
+    }
+}
@@ -0,0 +1,70 @@
+use image::{Rgba, RgbaImage};
+
+/// Software rendered RGBA8 pixels, uploaded to a texture once complete
+pub struct Frame {
+    image: RgbaImage,
+}
+
+impl Frame {
+    pub fn new(width: u32, height: u32) -> Frame {
+        Frame {
+            image: RgbaImage::new(width.max(1), height.max(1)),
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.image.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.image.height()
+    }
+
+    pub fn image(&self) -> &RgbaImage {
+        &self.image
+    }
+
+    /// Raw RGBA8 bytes, row by row
+    pub fn pixels_mut(&mut self) -> &mut [u8] {
+        &mut self.image
+    }
+
+    /// Reallocates only when the size changed
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if self.width() != width.max(1) || self.height() != height.max(1) {
+            *self = Frame::new(width, height);
+        }
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> [u8; 4] {
+        self.image.get_pixel(x, y).0
+    }
+
+    pub fn set(&mut self, x: u32, y: u32, color: [u8; 4]) {
+        self.image.put_pixel(x, y, Rgba(color));
+    }
+
+    /// Alpha blends `color` over the current pixel, the result is opaque
+    pub fn blend(&mut self, x: u32, y: u32, color: [f32; 4]) {
+        let pixel = self.image.get_pixel_mut(x, y);
+        let alpha = color[3];
+        for (channel, &over) in pixel.0.iter_mut().zip(color.iter()).take(3) {
+            let under = f32::from(*channel) / 255.0;
+            *channel = to_u8(over * alpha + under * (1.0 - alpha));
+        }
+        pixel.0[3] = 255;
+    }
+
+    /// Fills rows `top..bottom` of columns `left..right`, clipped to the frame
+    pub fn fill_rect(&mut self, left: u32, top: u32, right: u32, bottom: u32, color: [f32; 4]) {
+        for y in top..bottom.min(self.height()) {
+            for x in left..right.min(self.width()) {
+                self.blend(x, y, color);
+            }
+        }
+    }
+}
+
+fn to_u8(channel: f32) -> u8 {
+    (channel.clamp(0.0, 1.0) * 255.0).round() as u8
+}
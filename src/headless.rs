@@ -0,0 +1,93 @@
+use board::Board;
+use camera::Camera;
+use image::{imageops, RgbaImage};
+use layout::Rect;
+use renderer::RenderOptions;
+
+/// Renders every camera the same way the window does and pastes the frames
+/// into one image covering `area`
+pub fn render_views(
+    cameras: &mut [Camera],
+    board: &Board,
+    options: &RenderOptions,
+    area: Rect,
+) -> RgbaImage {
+    let mut image = RgbaImage::new(area.width.max(1.0) as u32, area.height.max(1.0) as u32);
+    for camera in cameras.iter_mut() {
+        camera.render(board, options);
+        let x = (camera.view.x - area.x) as u32;
+        let y = (camera.view.y - area.y) as u32;
+        imageops::replace(&mut image, camera.frame.image(), x, y);
+    }
+    image
+}
+
+pub fn save(image: &RgbaImage, path: &str) -> Result<(), String> {
+    image
+        .save(path)
+        .map_err(|e| format!("could not write '{}': {}", path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use input::KeyBindings;
+    use map;
+    use map::picture::Legend;
+    use player::Player;
+    use postfx::PostChain;
+    use stereo::{StereoConfig, StereoMode};
+    use tiles::TileRegistry;
+
+    fn render(parallel: bool, stereo: &StereoConfig) -> RgbaImage {
+        let mut map = map::load("assets/maps/default.txt", 50.0, &Legend::default()).unwrap();
+        map.board.registry = TileRegistry::load("assets/tiles.ron").unwrap();
+        let area = Rect::new(0.0, 0.0, 320.0, 200.0);
+        let mut cameras = (0..2)
+            .map(|i| {
+                let spawn = map.spawn(i);
+                let player = Player {
+                    position: spawn.position,
+                    // off the grid axes, so rays hit walls at every kind of angle
+                    angle: spawn.angle + 0.4,
+                    angle_tick: 0.0,
+                    rays: Vec::new(),
+                    move_step: 0.0,
+                    parallel,
+                    route: Vec::new(),
+                };
+                let mut camera = Camera::new(player, KeyBindings::for_player(i));
+                let view = Rect::new(0.0, 100.0 * i as f64, 320.0, 100.0);
+                camera.set_view(view, stereo.mode);
+                camera.update(&map.board, stereo);
+                camera
+            })
+            .collect::<Vec<Camera>>();
+        let post = PostChain::default();
+        let options = RenderOptions {
+            stereo_mode: stereo.mode,
+            post: &post,
+            indexed: None,
+        };
+        render_views(&mut cameras, &map.board, &options, area)
+    }
+
+    #[test]
+    fn parallel_and_single_threaded_frames_match() {
+        let modes = [
+            StereoMode::Off,
+            StereoMode::SideBySide,
+            StereoMode::Anaglyph,
+        ];
+        for &mode in modes.iter() {
+            let stereo = StereoConfig {
+                mode,
+                ..StereoConfig::default()
+            };
+            let parallel = render(true, &stereo);
+            let single = render(false, &stereo);
+            assert_eq!(parallel.dimensions(), single.dimensions());
+            assert!(*parallel == *single, "{:?} frames differ", mode);
+        }
+    }
+}
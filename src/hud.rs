@@ -0,0 +1,187 @@
+use colors;
+use graphics::{math::Matrix2d, Transformed};
+use layout::Rect;
+use sharp_graphics::{FontId, SharpGraphics};
+
+/// Corner of the hud area a text box is pinned to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Anchor {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl Anchor {
+    pub fn parse(value: &str) -> Option<Anchor> {
+        match value {
+            "top-left" => Some(Anchor::TopLeft),
+            "top-right" => Some(Anchor::TopRight),
+            "bottom-left" => Some(Anchor::BottomLeft),
+            "bottom-right" => Some(Anchor::BottomRight),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TextBox {
+    pub anchor: Anchor,
+    /// distance from the anchored corner, towards the middle of the area
+    pub margin: f64,
+    pub padding: f64,
+    pub font: FontId,
+    pub font_size: u32,
+    /// extra space between lines, added to `font_size`
+    pub line_gap: f64,
+    pub color: [f32; 4],
+    /// panel drawn behind the text
+    pub background: Option<[f32; 4]>,
+    /// widest the box may grow, lines longer than this are wrapped
+    pub max_width: Option<f64>,
+}
+
+impl Default for TextBox {
+    fn default() -> TextBox {
+        TextBox {
+            anchor: Anchor::TopLeft,
+            margin: 10.0,
+            padding: 4.0,
+            font: FontId::default(),
+            font_size: 15,
+            line_gap: 0.0,
+            color: colors::BLACK,
+            background: None,
+            max_width: None,
+        }
+    }
+}
+
+impl TextBox {
+    fn line_height(&self) -> f64 {
+        f64::from(self.font_size) + self.line_gap
+    }
+
+    /// Wraps `lines` to fit the box inside `area` and draws them, returning the
+    /// rectangle the box covered
+    pub fn draw(
+        &self,
+        transform: Matrix2d,
+        graphics: &mut SharpGraphics,
+        area: Rect,
+        lines: &[String],
+    ) -> Rect {
+        let inner_limit = (area.width - self.margin * 2.0 - self.padding * 2.0).max(1.0);
+        let max_text_width = self.max_width.map_or(inner_limit, |max_width| {
+            (max_width - self.padding * 2.0).clamp(1.0, inner_limit)
+        });
+        let (font, font_size) = (self.font, self.font_size);
+        let mut measure = |text: &str| graphics.text_width(font, font_size, text);
+        let wrapped: Vec<String> = lines
+            .iter()
+            .flat_map(|line| wrap(line, max_text_width, &mut measure))
+            .collect();
+        let text_width = wrapped.iter().map(|line| measure(line)).fold(0.0, f64::max);
+
+        let width = text_width + self.padding * 2.0;
+        let height = wrapped.len() as f64 * self.line_height() + self.padding * 2.0;
+        let x = match self.anchor {
+            Anchor::TopLeft | Anchor::BottomLeft => area.x + self.margin,
+            Anchor::TopRight | Anchor::BottomRight => area.x + area.width - self.margin - width,
+        };
+        let y = match self.anchor {
+            Anchor::TopLeft | Anchor::TopRight => area.y + self.margin,
+            Anchor::BottomLeft | Anchor::BottomRight => area.y + area.height - self.margin - height,
+        };
+        let panel = Rect::new(x, y, width, height);
+
+        if let Some(background) = self.background {
+            graphics.draw_rectangle(background, panel.to_array(), transform);
+        }
+        for (i, line) in wrapped.iter().enumerate() {
+            // text is drawn from its baseline
+            let baseline = y + self.padding + (i + 1) as f64 * self.line_height();
+            let location = transform.trans(x + self.padding, baseline);
+            graphics.draw_text(self.color, self.font, self.font_size, line, location);
+        }
+        panel
+    }
+}
+
+/// Splits `text` into lines no wider than `max_width` as reported by `measure`,
+/// breaking between words where possible and between characters otherwise.
+/// Runs of whitespace become a single space.
+pub fn wrap<F: FnMut(&str) -> f64>(text: &str, max_width: f64, mut measure: F) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let candidate = if line.is_empty() {
+            word.to_string()
+        } else {
+            format!("{} {}", line, word)
+        };
+        if measure(&candidate) <= max_width {
+            line = candidate;
+            continue;
+        }
+        if !line.is_empty() {
+            lines.push(std::mem::take(&mut line));
+        }
+        // a single word wider than the box is broken at character boundaries
+        for c in word.chars() {
+            line.push(c);
+            if measure(&line) > max_width && line.chars().count() > 1 {
+                line.pop();
+                lines.push(std::mem::take(&mut line));
+                line.push(c);
+            }
+        }
+    }
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every character one unit wide, however many bytes it takes
+    fn chars(text: &str) -> f64 {
+        text.chars().count() as f64
+    }
+
+    #[test]
+    fn multibyte_words_are_measured_by_character() {
+        assert_eq!(
+            wrap("héllo wörld ñ", 5.0, chars),
+            vec!["héllo", "wörld", "ñ"]
+        );
+        assert_eq!(wrap("ñ ñ ñ", 3.0, chars), vec!["ñ ñ", "ñ"]);
+    }
+
+    #[test]
+    fn words_wider_than_the_box_break_between_characters() {
+        assert_eq!(
+            wrap("ab abcdefgh", 3.0, chars),
+            vec!["ab", "abc", "def", "gh"]
+        );
+        assert_eq!(wrap("ééééé", 2.0, chars), vec!["éé", "éé", "é"]);
+        // a box too narrow for one character still gets one per line
+        assert_eq!(wrap("abc", 0.5, chars), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn empty_text_is_one_empty_line() {
+        assert_eq!(wrap("", 10.0, chars), vec![""]);
+        assert_eq!(wrap("   ", 10.0, chars), vec![""]);
+    }
+
+    #[test]
+    fn repeated_spaces_collapse() {
+        assert_eq!(wrap("a   b", 10.0, chars), vec!["a b"]);
+        assert_eq!(wrap("  a  b  ", 10.0, chars), vec!["a b"]);
+        assert_eq!(wrap("aaa    bbb", 3.0, chars), vec!["aaa", "bbb"]);
+    }
+}
@@ -0,0 +1,105 @@
+use piston::input::Key;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    MoveForward,
+    MoveBackward,
+    StrafeLeft,
+    StrafeRight,
+    TurnLeft,
+    TurnRight,
+    StepUp,
+    StepDown,
+}
+
+/// Keys driving one camera, `None` leaves the action unbound
+#[derive(Debug, Clone)]
+pub struct KeyBindings {
+    pub move_forward: Option<Key>,
+    pub move_backward: Option<Key>,
+    pub strafe_left: Option<Key>,
+    pub strafe_right: Option<Key>,
+    pub turn_left: Option<Key>,
+    pub turn_right: Option<Key>,
+    pub step_up: Option<Key>,
+    pub step_down: Option<Key>,
+}
+
+impl KeyBindings {
+    /// Default bindings for up to four local players
+    pub fn for_player(player_index: usize) -> KeyBindings {
+        match player_index {
+            0 => KeyBindings {
+                move_forward: Some(Key::W),
+                move_backward: Some(Key::S),
+                strafe_left: Some(Key::A),
+                strafe_right: Some(Key::D),
+                turn_left: Some(Key::Left),
+                turn_right: Some(Key::Right),
+                step_up: Some(Key::Up),
+                step_down: Some(Key::Down),
+            },
+            1 => KeyBindings {
+                move_forward: Some(Key::NumPad8),
+                move_backward: Some(Key::NumPad5),
+                strafe_left: Some(Key::NumPad4),
+                strafe_right: Some(Key::NumPad6),
+                turn_left: Some(Key::NumPad7),
+                turn_right: Some(Key::NumPad9),
+                step_up: None,
+                step_down: None,
+            },
+            2 => KeyBindings {
+                move_forward: Some(Key::T),
+                move_backward: Some(Key::G),
+                strafe_left: Some(Key::F),
+                strafe_right: Some(Key::H),
+                turn_left: Some(Key::R),
+                turn_right: Some(Key::Y),
+                step_up: None,
+                step_down: None,
+            },
+            3 => KeyBindings {
+                move_forward: Some(Key::I),
+                move_backward: Some(Key::K),
+                strafe_left: Some(Key::J),
+                strafe_right: Some(Key::L),
+                turn_left: Some(Key::U),
+                turn_right: Some(Key::O),
+                step_up: None,
+                step_down: None,
+            },
+            _ => KeyBindings::unbound(),
+        }
+    }
+
+    pub fn unbound() -> KeyBindings {
+        KeyBindings {
+            move_forward: None,
+            move_backward: None,
+            strafe_left: None,
+            strafe_right: None,
+            turn_left: None,
+            turn_right: None,
+            step_up: None,
+            step_down: None,
+        }
+    }
+
+    pub fn action(&self, key: Key) -> Option<Action> {
+        let bindings = [
+            (self.move_forward, Action::MoveForward),
+            (self.move_backward, Action::MoveBackward),
+            (self.strafe_left, Action::StrafeLeft),
+            (self.strafe_right, Action::StrafeRight),
+            (self.turn_left, Action::TurnLeft),
+            (self.turn_right, Action::TurnRight),
+            (self.step_up, Action::StepUp),
+            (self.step_down, Action::StepDown),
+        ];
+        bindings
+            .iter()
+            .find(|(bound, _)| *bound == Some(key))
+            .map(|&(_, action)| action)
+    }
+}
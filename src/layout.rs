@@ -0,0 +1,169 @@
+use point::ScreenPoint;
+
+/// Screen space rectangle in window points
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Rect {
+    pub fn new(x: f64, y: f64, width: f64, height: f64) -> Rect {
+        Rect {
+            x,
+            y,
+            width: width.max(0.0),
+            height: height.max(0.0),
+        }
+    }
+
+    pub fn contains(&self, point: ScreenPoint) -> bool {
+        point.x >= self.x
+            && point.y >= self.y
+            && point.x < self.x + self.width
+            && point.y < self.y + self.height
+    }
+
+    pub fn to_array(self) -> [f64; 4] {
+        [self.x, self.y, self.width, self.height]
+    }
+
+    pub fn center(&self) -> ScreenPoint {
+        ScreenPoint {
+            x: self.x + self.width / 2.0,
+            y: self.y + self.height / 2.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LayoutMode {
+    /// 2d map on the left, 3d view, mini map and hud stacked on the right
+    Split,
+    /// 3d view fills the window, mini map and hud are drawn over it
+    FullView,
+}
+
+impl LayoutMode {
+    pub fn parse(value: &str) -> Option<LayoutMode> {
+        match value {
+            "split" => Some(LayoutMode::Split),
+            "full" | "fullview" => Some(LayoutMode::FullView),
+            _ => None,
+        }
+    }
+
+    pub fn next(self) -> LayoutMode {
+        match self {
+            LayoutMode::Split => LayoutMode::FullView,
+            LayoutMode::FullView => LayoutMode::Split,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LayoutConfig {
+    pub mode: LayoutMode,
+    /// share of the window width given to the 2d map in split mode
+    pub map_width_fraction: f64,
+    /// 3d view height / width in split mode
+    pub view_aspect: f64,
+    /// edge length of the square mini map panel
+    pub mini_map_size: f64,
+}
+
+impl Default for LayoutConfig {
+    fn default() -> LayoutConfig {
+        LayoutConfig {
+            mode: LayoutMode::Split,
+            map_width_fraction: 0.5,
+            view_aspect: 0.75,
+            mini_map_size: 250.0,
+        }
+    }
+}
+
+/// Placement of every panel for the current window size
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layout {
+    pub window: Rect,
+    pub map_2d: Option<Rect>,
+    pub view_3d: Rect,
+    pub mini_map: Rect,
+    pub hud: Rect,
+}
+
+impl Layout {
+    pub fn new(config: &LayoutConfig, window_size: [f64; 2]) -> Layout {
+        let [width, height] = window_size;
+        let window = Rect::new(0.0, 0.0, width, height);
+        match config.mode {
+            LayoutMode::Split => {
+                let map_width = (width * config.map_width_fraction).floor();
+                let side_x = map_width;
+                let side_width = width - map_width;
+                let view_height = (side_width * config.view_aspect).min(height).floor();
+                let mini_map_size = config
+                    .mini_map_size
+                    .min(side_width)
+                    .min(height - view_height);
+                Layout {
+                    window,
+                    map_2d: Some(Rect::new(0.0, 0.0, map_width, height)),
+                    view_3d: Rect::new(side_x, 0.0, side_width, view_height),
+                    mini_map: Rect::new(
+                        side_x + (side_width - mini_map_size) / 2.0,
+                        view_height,
+                        mini_map_size,
+                        mini_map_size,
+                    ),
+                    hud: Rect::new(
+                        side_x,
+                        view_height + mini_map_size,
+                        side_width,
+                        height - view_height - mini_map_size,
+                    ),
+                }
+            }
+            LayoutMode::FullView => {
+                let mini_map_size = config.mini_map_size.min(width).min(height);
+                Layout {
+                    window,
+                    map_2d: None,
+                    view_3d: window,
+                    mini_map: Rect::new(width - mini_map_size, 0.0, mini_map_size, mini_map_size),
+                    hud: Rect::new(0.0, 0.0, width - mini_map_size, height),
+                }
+            }
+        }
+    }
+
+    /// Splits the 3d view between `count` cameras, side by side for two, the
+    /// first across the top and two below it for three, and a 2x2 grid for four
+    pub fn camera_views(&self, count: usize) -> Vec<Rect> {
+        let view = self.view_3d;
+        let rows: &[usize] = match count {
+            0 | 1 => &[1],
+            2 => &[2],
+            3 => &[1, 2],
+            _ => &[2, 2],
+        };
+        let height = (view.height / rows.len() as f64).floor();
+        rows.iter()
+            .enumerate()
+            .flat_map(|(row, &columns)| {
+                let width = (view.width / columns as f64).floor();
+                (0..columns).map(move |column| {
+                    Rect::new(
+                        view.x + column as f64 * width,
+                        view.y + row as f64 * height,
+                        width,
+                        height,
+                    )
+                })
+            })
+            .collect()
+    }
+}
@@ -1,64 +1,281 @@
 extern crate glutin_window;
 extern crate graphics;
+extern crate image;
 extern crate opengl_graphics;
 extern crate piston;
+extern crate ray_casting_fixed;
+extern crate rayon;
+extern crate ron;
+extern crate roxmltree;
+extern crate serde;
+extern crate serde_json;
 
-use board::Board;
+use board::Tile;
+use camera::Camera;
+use config::Config;
+use debug_overlay::{DebugLayers, Layer};
+use decal::DecalKind;
+use editor::Editor;
 use glutin_window::GlutinWindow as Window;
-use graphics::{math::Matrix2d, Transformed};
+use graphics::Transformed;
+use hud::TextBox;
+use input::KeyBindings;
+use layout::{Layout, LayoutConfig, Rect};
+use map::generate::generate;
+use map::picture::Legend;
+use map_view::MapCamera;
 use mini_map::MiniMap;
 use opengl_graphics::{Filter, GlGraphics, GlyphCache, OpenGL, TextureSettings};
+use palette::IndexedColors;
+use path::Movement;
 use piston::event_loop::{EventSettings, Events};
 use piston::input::{
-    Button, Key, MouseCursorEvent, PressEvent, RenderArgs, RenderEvent, UpdateArgs, UpdateEvent,
+    Button, Key, MouseButton, MouseCursorEvent, MouseScrollEvent, PressEvent, ReleaseEvent,
+    RenderArgs, RenderEvent, ResizeEvent, UpdateArgs, UpdateEvent,
 };
 use piston::window::WindowSettings;
-use point::{BoardPoint, ScreenPoint};
-use ray::Ray;
+use player::Player;
+use point::ScreenPoint;
+use postfx::PostChain;
+use renderer::RenderOptions;
 use sharp_graphics::SharpGraphics;
+use stats::Stats;
+use std::path::Path;
+use std::time::Instant;
+use stereo::StereoConfig;
+use tiles::TileRegistry;
 
+mod animation;
 mod board;
+mod camera;
 mod colors;
+mod config;
+mod debug_overlay;
+mod decal;
 mod display_vec;
+mod editor;
+mod fixed_compare;
+mod frame;
+mod headless;
+mod hud;
+mod input;
+mod layout;
+mod map;
+mod map_view;
 mod maths;
 mod mini_map;
+mod palette;
+mod path;
 mod player;
 mod point;
+mod postfx;
 mod ray;
+mod renderer;
 mod sharp_graphics;
+mod sight;
+mod stats;
+mod stereo;
+#[cfg(test)]
+mod test_board;
+mod tiles;
 
 #[cfg(target_os = "linux")]
 static TOP_OFFSET: f64 = 30.0;
 #[cfg(target_os = "windows")]
 static TOP_OFFSET: f64 = 0.0;
 
+/// Palette used when indexed rendering is switched on without `--palette`
+const DEFAULT_PALETTE: &str = "assets/default.pal";
+/// Tile kinds used when `--tiles` isn't given
+const DEFAULT_TILES: &str = "assets/tiles.ron";
+/// Map played when `--map` isn't given
+const DEFAULT_MAP: &str = "assets/maps/default.txt";
+/// Pixels per tile on the 2d map
+const BLOCK_SIZE: f64 = 50.0;
+/// Tiles per second walked by a player following a click to move route
+const ROUTE_SPEED: f64 = 3.0;
+
 fn main() {
-    let opengl = OpenGL::V3_2;
-    let mut window: Window = WindowSettings::new("ray-casting", [1100, 1100])
-        .graphics_api(opengl)
-        .exit_on_esc(true)
-        .build()
-        .unwrap();
+    let config = match Config::from_args(std::env::args().skip(1)) {
+        Ok(config) => config,
+        Err(message) => {
+            eprintln!("ray-casting: {}", message);
+            std::process::exit(2);
+        }
+    };
 
-    // window.set_capture_cursor(capture_cursor); // doesn't work w/ current window type
+    let legend = match config.map_legend {
+        Some(ref path) => std::fs::read_to_string(path)
+            .map_err(|e| format!("could not read '{}': {}", path, e))
+            .and_then(|text| Legend::parse(&text).map_err(|e| format!("{}: {}", path, e))),
+        None => Ok(Legend::default()),
+    };
+    let legend = match legend {
+        Ok(legend) => legend,
+        Err(message) => {
+            eprintln!("ray-casting: {}", message);
+            std::process::exit(2);
+        }
+    };
+    // generated maps are named after their seed, which is where the editor saves them
+    let mut map_path = config
+        .map
+        .clone()
+        .unwrap_or_else(|| DEFAULT_MAP.to_string());
+    let map = match config.generate {
+        Some(generator) => {
+            let seed = config.seed.unwrap_or_else(|| {
+                std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|time| time.as_secs())
+                    .unwrap_or_default()
+            });
+            println!("ray-casting: {} seed {}", generator.name(), seed);
+            map_path = format!("{}-{}.ron", generator.name(), seed);
+            let [tiles_x, tiles_y] = config.map_size.unwrap_or([32, 32]);
+            generate(
+                generator,
+                seed,
+                tiles_x as usize,
+                tiles_y as usize,
+                BLOCK_SIZE,
+            )
+        }
+        None => map::load(&map_path, BLOCK_SIZE, &legend),
+    };
+    let mut map = match map {
+        Ok(map) => map,
+        Err(message) => {
+            eprintln!("ray-casting: {}", message);
+            std::process::exit(2);
+        }
+    };
 
-    let texture_settings = TextureSettings::new().filter(Filter::Nearest);
-    let glyphs = GlyphCache::new("assets/FiraSans-Regular.ttf", (), texture_settings)
-        .expect("Could not load font");
+    map.board.out_of_bounds = config.out_of_bounds;
+    map.board.registry = match TileRegistry::load(config.tiles.as_deref().unwrap_or(DEFAULT_TILES))
+    {
+        Ok(registry) => registry,
+        Err(message) => {
+            eprintln!("ray-casting: {}", message);
+            std::process::exit(2);
+        }
+    };
 
-    let mut graphics = SharpGraphics::new(GlGraphics::new(opengl), glyphs);
-    const TILES_X: usize = 10;
-    const TILES_Y: usize = 20;
-    const BLOCK_SIZE: f64 = 50.0;
+    if config.check_map {
+        let problems = map::validate::validate(&map);
+        for problem in problems.iter() {
+            println!("{}: {}", map_path, problem);
+        }
+        println!("{}: {} problems", map_path, problems.len());
+        std::process::exit(if problems.is_empty() { 0 } else { 1 });
+    }
+    // the game still runs, drawing them in `colors::UNKNOWN_TILE`
+    for problem in map::validate::unknown_tiles(&map.board) {
+        eprintln!("ray-casting: {}: {}", map_path, problem);
+    }
+
+    if let Some(ref path) = config.save_map {
+        if let Err(message) = map::save(&map, path) {
+            eprintln!("ray-casting: {}", message);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if config.fixed_compare {
+        const ANGLE_STEP: u16 = 3;
+        let report = match fixed_compare::compare(&map.board, ANGLE_STEP) {
+            Ok(report) => report,
+            Err(message) => {
+                eprintln!("ray-casting: {}", message);
+                std::process::exit(2);
+            }
+        };
+        for mismatch in report.mismatches.iter() {
+            println!("mismatch {}", mismatch);
+        }
+        println!(
+            "fixed compare: {} rays, {} mismatches",
+            report.rays,
+            report.mismatches.len()
+        );
+        std::process::exit(if report.mismatches.is_empty() { 0 } else { 1 });
+    }
+
+    if let Some(threads) = config.threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+            .expect("could not build ray casting thread pool");
+    }
+
+    let indexed_colors = match config.palette {
+        Some(ref path) => match IndexedColors::load(path) {
+            Ok(colors) => Some(colors),
+            Err(message) => {
+                eprintln!("ray-casting: {}", message);
+                std::process::exit(2);
+            }
+        },
+        None => IndexedColors::load(DEFAULT_PALETTE).ok(),
+    }
+    .map(|colors| colors.with_fog(map.board.info.fog));
+    if let Some(ref colors) = indexed_colors {
+        map.board.index_textures(&colors.palette);
+    }
+
+    let window_size = [
+        f64::from(config.window_size[0]),
+        f64::from(config.window_size[1]),
+    ];
+    let layout = Layout::new(&config.layout, window_size);
+    let cameras = (0..config.players)
+        .map(|i| {
+            let spawn = map.spawn(i);
+            let player = Player {
+                position: spawn.position,
+                angle: spawn.angle,
+                angle_tick: std::f64::consts::PI / -20.0,
+                rays: Vec::new(),
+                move_step: 0.1,
+                parallel: config.parallel,
+                route: Vec::new(),
+            };
+            Camera::new(player, KeyBindings::for_player(i))
+        })
+        .collect();
+    // a new file next to a loaded map, so saving never overwrites the map itself
+    let edit_save = config.edit_save.clone().unwrap_or_else(|| {
+        if config.generate.is_some() {
+            return map_path.clone();
+        }
+        let path = Path::new(&map_path);
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        path.with_file_name(format!("{}.edited.ron", stem))
+            .to_string_lossy()
+            .into_owned()
+    });
     let mut app = App {
-        board: board::Board::new(load_board(), TILES_X, TILES_Y, BLOCK_SIZE),
+        editor: Editor::new(edit_save, map.spawns.clone(), map.entities.clone()),
+        path_movement: config.path_movement,
+        board: map.board,
         mini_map: MiniMap {},
-        player: player::Player {
-            position: BoardPoint { x: 6.0, y: 3.0 },
-            angle: 0.0,
-            angle_tick: std::f64::consts::PI / -20.0,
-            rays: vec![Ray::new(); 400],
-            move_step: 0.1,
+        cameras,
+        layout,
+        layout_config: config.layout,
+        stereo: config.stereo,
+        post: config.post,
+        indexed: config.palette.is_some(),
+        indexed_colors,
+        debug: DebugLayers::default(),
+        map_camera: MapCamera::default(),
+        stats: Stats::default(),
+        decal_kind: DecalKind::Sign,
+        hud: TextBox {
+            anchor: config.hud_anchor,
+            font_size: config.hud_font_size,
+            background: Some(colors::HUD_PANEL),
+            ..TextBox::default()
         },
         dt: 0.0,
         fps: 0.0,
@@ -66,17 +283,61 @@ fn main() {
         mouse_y: 0.0,
     };
 
+    app.resize(window_size);
     app.update(UpdateArgs { dt: 0.0 });
+
+    if let Some(path) = config.headless {
+        let options = RenderOptions {
+            stereo_mode: app.stereo.mode,
+            post: &app.post,
+            indexed: app.indexed_colors.as_ref().filter(|_| app.indexed),
+        };
+        let image =
+            headless::render_views(&mut app.cameras, &app.board, &options, app.layout.view_3d);
+        if let Err(message) = headless::save(&image, &path) {
+            eprintln!("ray-casting: {}", message);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let opengl = OpenGL::V3_2;
+    let mut window: Window = WindowSettings::new("ray-casting", config.window_size)
+        .graphics_api(opengl)
+        .exit_on_esc(true)
+        .build()
+        .unwrap();
+
+    // window.set_capture_cursor(capture_cursor); // doesn't work w/ current window type
+
+    let texture_settings = TextureSettings::new().filter(Filter::Nearest);
+    let glyphs = GlyphCache::new("assets/FiraSans-Regular.ttf", (), texture_settings)
+        .expect("Could not load font");
+
+    let mut graphics = SharpGraphics::new(GlGraphics::new(opengl), glyphs);
+    if let Some(ref path) = config.hud_font {
+        match GlyphCache::new(path, (), texture_settings) {
+            Ok(glyphs) => app.hud.font = graphics.add_font(glyphs),
+            Err(e) => {
+                eprintln!("ray-casting: could not load font '{}': {}", path, e);
+                std::process::exit(2);
+            }
+        }
+    }
     let mut events = Events::new(EventSettings::new());
     while let Some(e) = events.next(&mut window) {
-        e.mouse_cursor(|pos| {
-            app.mouse_x = pos[0];
-            app.mouse_y = pos[1] - TOP_OFFSET;
-        });
+        if let Some(args) = e.resize_args() {
+            app.resize(args.window_size);
+        }
+        e.mouse_cursor(|pos| app.mouse_moved(pos[0], pos[1] - TOP_OFFSET));
+        e.mouse_scroll(|scroll| app.scroll(scroll[1]));
 
         if let Some(b) = e.press_args() {
             app.handle_input(&b);
         }
+        if let Some(b) = e.release_args() {
+            app.handle_release(&b);
+        }
         if let Some(u) = e.update_args() {
             app.update(u);
         }
@@ -87,9 +348,26 @@ fn main() {
 }
 
 struct App {
-    player: player::Player,
+    cameras: Vec<Camera>,
     board: board::Board,
     mini_map: MiniMap,
+    layout: Layout,
+    layout_config: LayoutConfig,
+    stereo: StereoConfig,
+    post: PostChain,
+    /// render through `indexed_colors` instead of RGBA
+    indexed: bool,
+    indexed_colors: Option<IndexedColors>,
+    debug: DebugLayers,
+    map_camera: MapCamera,
+    stats: Stats,
+    /// what the decal key sticks on the wall next
+    decal_kind: DecalKind,
+    /// debug text box in the hud area
+    hud: TextBox,
+    editor: Editor,
+    /// how click to move routes step between tiles
+    path_movement: Movement,
     mouse_x: f64,
     mouse_y: f64,
     dt: f64,
@@ -104,198 +382,293 @@ impl std::fmt::Display for App {
 
 impl App {
     fn render(&mut self, args: &RenderArgs, graphics: &mut SharpGraphics) {
+        let layout = self.layout.clone();
         graphics.draw(args.viewport(), |context, graphics| {
             graphics.clear([1.0; 4]);
 
-            self.board.draw(context.transform, graphics);
-            self.mini_map
-                .draw(context.transform, graphics, &self.board, &self.player);
-            self.player.draw(context.transform, graphics, &self.board);
-            let mouse_screen_point = ScreenPoint {
-                x: self.mouse_x,
-                y: self.mouse_y,
+            // 3d section
+            let options = RenderOptions {
+                stereo_mode: self.stereo.mode,
+                post: &self.post,
+                indexed: self.indexed_colors.as_ref().filter(|_| self.indexed),
             };
-            let mouse_board_point = self.board.point_from(mouse_screen_point);
-            let board_debug = format!(
-                "board_x: {}, board_y: {}",
-                mouse_board_point.x, mouse_board_point.y
-            );
-            let board_index = format!(
-                "index: {},",
-                self.board.get_index(BoardPoint {
-                    x: mouse_board_point.x,
-                    y: mouse_board_point.y,
-                })
-            );
-            let mouse_debug = format!("mouse_x: {}, mouse_y: {}", self.mouse_x, self.mouse_y);
-            let mut display_vector = vec![
-                board_debug,
-                board_index,
-                mouse_debug,
-                self.to_string(),
-                self.player.to_string(),
-            ];
-            display_vector.push(format!("sin: {}", self.player.angle.sin()));
-            display_vector.push(format!("cos: {}", self.player.angle.cos()));
-            display_vector.push(format!("tan: {}", self.player.angle.tan()));
-            // display_vector.push(format!("x-es: {}", self.player.rays[0].x_intercepts));
-            // display_vector.push(format!("y-es: {}", self.player.rays[0].y_intercepts));
-            draw_lines(context.transform, graphics, &self.board, display_vector);
+            let started = Instant::now();
+            for (slot, camera) in self.cameras.iter_mut().enumerate() {
+                camera.render(&self.board, &options);
+                let view = camera.view;
+                graphics.draw_frame(slot, &camera.frame, context.transform.trans(view.x, view.y));
+            }
+            self.stats.record_3d(started.elapsed());
 
-            // 3d section
-            // 3d ceil
-            const VIEW_WIDTH: f64 = 400.0;
-            const VIEW_HEIGHT: f64 = 300.0;
-            const VIEW_HEIGHT_HALF: f64 = VIEW_HEIGHT / 2.0;
-            graphics.draw_rectangle(
-                colors::GRAY_CEIL,
-                [0.0, 0.0, VIEW_WIDTH, VIEW_HEIGHT_HALF],
-                context.transform.trans(600.0, 0.0),
-            );
-            // 3d floor
-            graphics.draw_rectangle(
-                colors::GRAY_FLOOR,
-                [0.0, 0.0, VIEW_WIDTH, 150.0],
-                context.transform.trans(600.0, VIEW_HEIGHT_HALF),
-            );
-            // 3d wall
-            self.draw_3d_wall(
-                &self.player.rays,
-                VIEW_HEIGHT_HALF,
-                graphics,
+            let started = Instant::now();
+            if let Some(map_2d) = layout.map_2d {
+                let map_transform = self.map_camera.transform(context.transform, map_2d);
+                graphics.set_clip(Some(map_2d.to_array()));
+                self.board
+                    .draw(map_transform, graphics, self.debug.is_on(Layer::Grid));
+                for camera in self.cameras.iter() {
+                    camera
+                        .player
+                        .draw(map_transform, graphics, &self.board, &self.debug);
+                }
+                if self.editor.enabled && map_2d.contains(self.mouse_point()) {
+                    self.editor.draw_cursor(
+                        map_transform,
+                        graphics,
+                        &self.board,
+                        self.mouse_map_point(),
+                    );
+                }
+                graphics.set_clip(None);
+            }
+            self.stats.record_2d(started.elapsed());
+
+            let started = Instant::now();
+            let player = &self.cameras[0].player;
+            self.mini_map.draw(
                 context.transform,
+                graphics,
+                layout.mini_map,
+                &self.board,
+                player,
             );
+            self.stats.record_mini_map(started.elapsed());
+
+            let mut display_vector = Vec::new();
+            if self.debug.is_on(Layer::MouseProbe) {
+                let mouse_screen_point = self.mouse_map_point();
+                let mouse_board_point = self.board.point_from(mouse_screen_point);
+                display_vector.push(format!(
+                    "board_x: {}, board_y: {}",
+                    mouse_board_point.x, mouse_board_point.y
+                ));
+                let index = self.board.get_index(mouse_board_point);
+                display_vector.push(match index {
+                    Some(index) => format!("index: {},", index),
+                    None => "index: off the board,".to_string(),
+                });
+                display_vector.push(format!(
+                    "mouse_x: {}, mouse_y: {}",
+                    self.mouse_x, self.mouse_y
+                ));
+                display_vector.push(format!(
+                    "seen: {}",
+                    sight::can_see(
+                        &self.board,
+                        self.cameras[0].player.position,
+                        mouse_board_point
+                    )
+                ));
+                if let Some(properties) = self
+                    .board
+                    .tile_at(mouse_board_point)
+                    .and_then(|tile| self.board.info.tile_properties(tile.id()))
+                {
+                    display_vector.push(format!("tile: {:?}", properties));
+                }
+            }
+            if self.debug.is_on(Layer::FpsText) {
+                display_vector.push(self.to_string());
+            }
+            if self.debug.is_on(Layer::Stats) {
+                display_vector.push(self.stats.to_string());
+            }
+            display_vector.extend(self.editor.describe(&self.board));
+            for camera in self.cameras.iter() {
+                display_vector.push(camera.player.to_string());
+            }
+            display_vector.push(self.post.to_string());
+            display_vector.push(format!(
+                "decals: {}, next: {:?}",
+                self.board.decals.len(),
+                self.decal_kind
+            ));
+            display_vector.push(self.debug.to_string());
+            if layout.map_2d.is_some() {
+                display_vector.push(self.map_camera.to_string());
+            }
+            display_vector.push(format!("sin: {}", player.angle.sin()));
+            display_vector.push(format!("cos: {}", player.angle.cos()));
+            display_vector.push(format!("tan: {}", player.angle.tan()));
+            // display_vector.push(format!("x-es: {}", player.rays[0].x_intercepts));
+            // display_vector.push(format!("y-es: {}", player.rays[0].y_intercepts));
+            self.hud
+                .draw(context.transform, graphics, layout.hud, &display_vector);
+
+            if self.debug.is_on(Layer::Stats) {
+                const GRAPH_SIZE: [f64; 2] = [240.0, 80.0];
+                const MARGIN: f64 = 10.0;
+                let graph = Rect::new(
+                    layout.hud.x + layout.hud.width - GRAPH_SIZE[0] - MARGIN,
+                    layout.hud.y + layout.hud.height - GRAPH_SIZE[1] - MARGIN,
+                    GRAPH_SIZE[0],
+                    GRAPH_SIZE[1],
+                );
+                self.stats.draw_graph(context.transform, graphics, graph);
+            }
         });
+        self.stats.finish_frame();
     }
 
-    fn draw_3d_wall(
-        &self,
-        rays: &Vec<Ray>,
-        view_height_half: f64,
-        graphics: &mut SharpGraphics,
-        transform: Matrix2d,
-    ) {
-        for (i, ray) in rays.iter().enumerate() {
-            let wall_height = ray.wall_height;
-            let trans_y = view_height_half - wall_height / 2.0;
-            let board_index = ray
-                .wall_intersection
-                .expect("bad intersection")
-                .board_index
-                .expect("bad index");
-            let color = match self.board.tiles[board_index] {
-                1 => colors::RED_ALPHA,
-                2 => colors::BLUE_ALPHA,
-                3 => colors::GREEN_ALPHA,
-                4 => colors::ORANGE_ALPHA,
-                _ => panic!("bad color"),
-            };
-            graphics.draw_line(
-                color,
-                [0.0, 0.0, 0.0, wall_height],
-                transform.trans(601.0 + i as f64, trans_y),
-            );
+    fn resize(&mut self, window_size: [f64; 2]) {
+        self.layout = Layout::new(&self.layout_config, window_size);
+        let views = self.layout.camera_views(self.cameras.len());
+        for (camera, view) in self.cameras.iter_mut().zip(views) {
+            camera.set_view(view, self.stereo.mode);
+            camera.update(&self.board, &self.stereo);
+        }
+    }
+
+    fn mouse_point(&self) -> ScreenPoint {
+        ScreenPoint {
+            x: self.mouse_x,
+            y: self.mouse_y,
+        }
+    }
+
+    /// Mouse position in 2d map space, through the map camera
+    fn mouse_map_point(&self) -> ScreenPoint {
+        match self.layout.map_2d {
+            Some(map_2d) => self.map_camera.to_map(map_2d, self.mouse_point()),
+            None => self.mouse_point(),
+        }
+    }
+
+    fn mouse_moved(&mut self, x: f64, y: f64) {
+        self.mouse_x = x;
+        self.mouse_y = y;
+        let cursor = self.mouse_point();
+        self.map_camera.drag_to(cursor);
+        let point = self.board.point_from(self.mouse_map_point());
+        self.editor.continue_stroke(&mut self.board, point);
+    }
+
+    /// Zooms the 2d map around the cursor while it is over the map
+    fn scroll(&mut self, notches: f64) {
+        let cursor = self.mouse_point();
+        if let Some(map_2d) = self.layout.map_2d.filter(|map_2d| map_2d.contains(cursor)) {
+            self.map_camera.zoom_at(map_2d, cursor, notches);
+        }
+    }
+
+    fn handle_release(&mut self, button: &Button) {
+        if let Button::Mouse(_) = button {
+            self.map_camera.end_drag();
+            self.editor.end_stroke();
         }
     }
 
     fn update(&mut self, args: UpdateArgs) {
         self.dt = args.dt;
         self.fps = 1.0 / self.dt;
-        self.player.update(&self.board);
+        self.board.update(args.dt);
+        self.cameras[0]
+            .player
+            .follow_route(ROUTE_SPEED * args.dt, &self.board);
+        for camera in self.cameras.iter_mut() {
+            let started = Instant::now();
+            camera.update(&self.board, &self.stereo);
+            self.stats.record_rays(camera.rays(), started.elapsed());
+        }
+        if let Some(map_2d) = self.layout.map_2d {
+            let position = self.cameras[0].player.position;
+            let target = ScreenPoint {
+                x: position.x * self.board.block_size,
+                y: position.y * self.board.block_size,
+            };
+            self.map_camera.follow(map_2d, target);
+        }
     }
 
     fn handle_input(&mut self, button: &Button) {
-        if let Button::Keyboard(key) = button {
-            match key {
-                Key::W => {
-                    self.player.move_forward(&self.board);
+        if let Button::Mouse(mouse_button) = button {
+            let cursor = self.mouse_point();
+            let on_map = self
+                .layout
+                .map_2d
+                .is_some_and(|map_2d| map_2d.contains(cursor));
+            let point = self.board.point_from(self.mouse_map_point());
+            // the editor paints with the left and right buttons and pans with the middle one,
+            // otherwise the left button pans and the right one walks the first player there
+            match (on_map, self.editor.enabled, mouse_button) {
+                (false, _, _) => {}
+                (true, true, MouseButton::Left) => {
+                    let tile = Tile(self.editor.tile);
+                    self.editor.start_stroke(&mut self.board, tile, point);
                 }
-                Key::S => {
-                    self.player.move_backward(&self.board);
+                (true, true, MouseButton::Right) => {
+                    self.editor
+                        .start_stroke(&mut self.board, Tile::EMPTY, point);
                 }
-                Key::A => {
-                    self.player.strafe_left(&self.board);
+                (true, true, MouseButton::Middle) | (true, false, MouseButton::Left) => {
+                    self.map_camera.start_drag(cursor);
                 }
-                Key::D => {
-                    self.player.strafe_right(&self.board);
+                (true, false, MouseButton::Right) => {
+                    let player = &mut self.cameras[0].player;
+                    player.route =
+                        path::find_path(&self.board, player.position, point, self.path_movement)
+                            .map(|waypoints| waypoints[1..].to_vec())
+                            .unwrap_or_default();
                 }
-                Key::Left => {
-                    self.player.angle += self.player.angle_tick;
+                (true, _, _) => {}
+            }
+        }
+        if let Button::Keyboard(key) = button {
+            if self.editor.enabled && self.editor.select(*key, &self.board) {
+                return;
+            }
+            for camera in self.cameras.iter_mut() {
+                if let Some(action) = camera.bindings.action(*key) {
+                    camera.player.act(action, &self.board);
+                }
+            }
+            if let Some(layer) = Layer::from_hotkey(*key) {
+                self.debug.toggle(layer);
+            }
+            match key {
+                Key::P => {
+                    for camera in self.cameras.iter_mut() {
+                        camera.player.parallel = !camera.player.parallel;
+                    }
+                }
+                Key::N => {
+                    self.stereo.mode = self.stereo.mode.next();
+                    let window_size = [self.layout.window.width, self.layout.window.height];
+                    self.resize(window_size);
                 }
-                Key::Right => {
-                    self.player.angle -= self.player.angle_tick;
+                Key::Z => self.post.toggle("gamma"),
+                Key::X => self.post.toggle("pixelate"),
+                Key::C => self.post.toggle("dither"),
+                Key::V => self.post.toggle("scanlines"),
+                Key::B => self.post.toggle("vignette"),
+                Key::E => {
+                    let player = &self.cameras[0].player;
+                    if let Some(ray) = player.rays.get(player.rays.len() / 2) {
+                        let block_size = self.board.block_size;
+                        if self
+                            .board
+                            .decals
+                            .place_at_hit(ray, block_size, self.decal_kind)
+                        {
+                            self.decal_kind = self.decal_kind.next();
+                        }
+                    }
                 }
-                Key::Up => {
-                    self.player.position.y -= 1.0;
+                Key::Delete => self.board.decals.clear(),
+                Key::Home => self.map_camera.toggle_follow(),
+                Key::End => self.map_camera = MapCamera::default(),
+                Key::M => self.editor.toggle(),
+                Key::F9 if self.editor.enabled => self.editor.save(&self.board),
+                Key::Q => {
+                    self.indexed = !self.indexed && self.indexed_colors.is_some();
                 }
-                Key::Down => {
-                    self.player.position.y += 1.0;
+                Key::Tab => {
+                    self.layout_config.mode = self.layout_config.mode.next();
+                    let window_size = [self.layout.window.width, self.layout.window.height];
+                    self.resize(window_size);
                 }
                 _ => {}
             }
         }
     }
 }
-
-fn draw_lines(
-    transform: Matrix2d,
-    graphics: &mut SharpGraphics,
-    board: &Board,
-    lines: Vec<String>,
-) {
-    let mut line_start = board.block_size * board.tiles_x as f64 + 25.0;
-    for line in lines.into_iter() {
-        line_start += draw_string(transform, graphics, line_start, line);
-    }
-}
-
-fn draw_string(
-    transform: Matrix2d,
-    graphics: &mut SharpGraphics,
-    line_start: f64,
-    data: String,
-) -> f64 {
-    const FONT_SIZE: u32 = 15;
-    const LINE_LENGTH: usize = 150;
-    const LINE_HEIGHT: f64 = 15.0;
-
-    let mut line_height_used = 0.0;
-    let mut end_index = 0;
-    while end_index != data.len() {
-        let start_index = end_index;
-        end_index += std::cmp::min(data.len() - end_index, LINE_LENGTH);
-        line_height_used += LINE_HEIGHT;
-
-        let location = transform.trans(10.0, line_start + line_height_used);
-        let slice = &data[start_index..end_index];
-        graphics.draw_text(colors::BLACK, FONT_SIZE, slice, location)
-    }
-
-    line_height_used
-}
-
-#[rustfmt::skip]
-fn load_board() -> Vec<u32> {
-   vec![
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        0, 2, 1, 1, 1, 1, 1, 4, 0, 0,
-        0, 2, 0, 0, 0, 0, 0, 4, 0, 0,
-        0, 2, 0, 0, 0, 0, 0, 4, 0, 0,
-        0, 2, 0, 0, 0, 0, 0, 4, 0, 0,
-        0, 2, 0, 0, 0, 0, 0, 4, 0, 0,
-        0, 2, 0, 0, 0, 0, 0, 4, 0, 0,
-        0, 2, 0, 0, 0, 0, 0, 4, 0, 0,
-        0, 2, 0, 0, 0, 0, 0, 4, 0, 0,
-        0, 2, 0, 0, 0, 0, 0, 4, 0, 0,
-        0, 2, 0, 0, 0, 0, 0, 4, 0, 0,
-        0, 2, 0, 0, 0, 0, 0, 4, 0, 0,
-        0, 2, 0, 0, 0, 0, 0, 4, 0, 0,
-        0, 2, 0, 0, 0, 0, 0, 4, 0, 0,
-        0, 2, 0, 0, 0, 0, 0, 4, 0, 0,
-        0, 2, 0, 0, 0, 0, 0, 4, 0, 0,
-        0, 2, 0, 0, 0, 0, 0, 4, 0, 0,
-        0, 2, 3, 3, 3, 3, 3, 4, 0, 0,
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-    ]
-}
@@ -0,0 +1,214 @@
+//! Plain text maps, one character per tile
+//!
+//! ```text
+//! # comment
+//! legend R 1
+//! map
+//! RRRR
+//! R>.R
+//! RRRR
+//! ```
+//!
+//! Everything after the `map` line is the grid, every row the same width.
+//! `.` and space are empty floor and the digits `0`-`9` are their own tile ids.
+//! A `legend` line maps any other character to a tile id. `^ > v <` mark a
+//! spawn facing north, east, south or west on an empty tile.
+
+use board::Board;
+use map::{Map, Spawn};
+use point::BoardPoint;
+use std::collections::HashMap;
+use std::f64::consts::{FRAC_PI_2, PI};
+
+const SPAWN_MARKERS: [(char, f64); 4] =
+    [('^', -FRAC_PI_2), ('>', 0.0), ('v', FRAC_PI_2), ('<', PI)];
+
+/// Parses a map, errors name the 1-based line they were found on
+pub fn parse(text: &str, block_size: f64) -> Result<Map, String> {
+    let mut legend: HashMap<char, u32> = HashMap::new();
+    legend.insert('.', 0);
+    legend.insert(' ', 0);
+    for digit in 0..10 {
+        let c = std::char::from_digit(digit, 10).expect("decimal digit");
+        legend.insert(c, digit);
+    }
+
+    let mut lines = text.lines().enumerate().map(|(i, line)| (i + 1, line));
+    let mut found_map = false;
+    for (number, line) in lines.by_ref() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if trimmed == "map" {
+            found_map = true;
+            break;
+        }
+        let mut words = trimmed.split_whitespace();
+        match (words.next(), words.next(), words.next(), words.next()) {
+            (Some("legend"), Some(symbol), Some(tile), None) => {
+                let mut chars = symbol.chars();
+                let c = match (chars.next(), chars.next()) {
+                    (Some(c), None) => c,
+                    _ => {
+                        return Err(format!(
+                            "line {}: legend symbol '{}' is not one character",
+                            number, symbol
+                        ))
+                    }
+                };
+                if SPAWN_MARKERS.iter().any(|&(marker, _)| marker == c) {
+                    return Err(format!("line {}: '{}' is a spawn marker", number, c));
+                }
+                let tile = tile
+                    .parse::<u32>()
+                    .map_err(|_| format!("line {}: bad tile id '{}'", number, tile))?;
+                legend.insert(c, tile);
+            }
+            _ => {
+                return Err(format!(
+                    "line {}: expected 'legend CHAR TILE' or 'map', found '{}'",
+                    number, trimmed
+                ))
+            }
+        }
+    }
+    if !found_map {
+        return Err("missing 'map' line".to_string());
+    }
+
+    let mut tiles = Vec::new();
+    let mut spawns = Vec::new();
+    let mut tiles_x = None;
+    let mut tiles_y = 0;
+    for (number, line) in lines {
+        // blank lines are skipped, a carriage return is left over from CRLF files
+        let row = line.trim_end_matches('\r');
+        if row.is_empty() {
+            continue;
+        }
+        let width = row.chars().count();
+        match tiles_x {
+            None => tiles_x = Some(width),
+            Some(expected) if expected != width => {
+                return Err(format!(
+                    "line {}: row is {} tiles wide, expected {} like the first row",
+                    number, width, expected
+                ));
+            }
+            Some(_) => {}
+        }
+        for (x, c) in row.chars().enumerate() {
+            if let Some(&(_, angle)) = SPAWN_MARKERS.iter().find(|&&(marker, _)| marker == c) {
+                spawns.push(Spawn {
+                    position: BoardPoint {
+                        x: x as f64 + 0.5,
+                        y: tiles_y as f64 + 0.5,
+                    },
+                    angle,
+                });
+                tiles.push(0);
+                continue;
+            }
+            match legend.get(&c) {
+                Some(&tile) => tiles.push(tile),
+                None => {
+                    return Err(format!(
+                        "line {}, column {}: unknown character '{}', add a 'legend {} TILE' line",
+                        number,
+                        x + 1,
+                        c,
+                        c
+                    ))
+                }
+            }
+        }
+        tiles_y += 1;
+    }
+
+    let tiles_x = tiles_x.ok_or_else(|| "map has no rows".to_string())?;
+    if spawns.is_empty() {
+        return Err("map has no spawn marker, place one of ^ > v <".to_string());
+    }
+    Ok(Map {
+        board: Board::new(tiles, tiles_x, tiles_y, block_size),
+        spawns,
+        entities: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(text: &str) -> String {
+        match parse(text, 50.0) {
+            Ok(_) => panic!("parsed '{}'", text),
+            Err(message) => message,
+        }
+    }
+
+    #[test]
+    fn parses_legend_digits_and_spawns() {
+        let map = parse("# a room\nlegend R 12\nmap\nRRRR\nR>3R\nRRRR\n", 50.0).unwrap();
+        assert_eq!((map.board.tiles_x, map.board.tiles_y), (4, 3));
+        assert_eq!(&map.board.tiles[4..8], &[12, 0, 3, 12]);
+        assert_eq!(map.spawns.len(), 1);
+        let position = map.spawns[0].position;
+        assert_eq!((position.x, position.y), (1.5, 1.5));
+        assert_eq!(map.spawns[0].angle, 0.0);
+    }
+
+    #[test]
+    fn ragged_rows_name_their_line() {
+        assert_eq!(
+            error("map\n111\n1>\n111\n"),
+            "line 3: row is 2 tiles wide, expected 3 like the first row"
+        );
+    }
+
+    #[test]
+    fn unknown_characters_name_their_line_and_column() {
+        assert_eq!(
+            error("legend R 1\n\nmap\nRRR\nR>X\n"),
+            "line 5, column 3: unknown character 'X', add a 'legend X TILE' line"
+        );
+    }
+
+    #[test]
+    fn a_map_line_is_required() {
+        assert_eq!(error("legend R 1\n"), "missing 'map' line");
+        assert_eq!(
+            error("legend R 1\nRRR\n"),
+            "line 2: expected 'legend CHAR TILE' or 'map', found 'RRR'"
+        );
+    }
+
+    #[test]
+    fn a_spawn_is_required() {
+        assert_eq!(
+            error("map\n111\n1.1\n111\n"),
+            "map has no spawn marker, place one of ^ > v <"
+        );
+        assert_eq!(error("map\n\n"), "map has no rows");
+    }
+
+    #[test]
+    fn crlf_files_parse_like_lf_files() {
+        let lf = parse("legend R 1\nmap\nRRR\nRvR\nRRR\n", 50.0).unwrap();
+        let crlf = parse("legend R 1\r\nmap\r\nRRR\r\nRvR\r\nRRR\r\n", 50.0).unwrap();
+        assert_eq!(crlf.board.tiles, lf.board.tiles);
+        assert_eq!(crlf.board.tiles_x, 3);
+        assert_eq!(crlf.spawns[0].angle, FRAC_PI_2);
+    }
+
+    #[test]
+    fn spawn_markers_cannot_be_legend_symbols() {
+        for &(marker, _) in SPAWN_MARKERS.iter() {
+            assert_eq!(
+                error(&format!("legend {} 1\nmap\n>\n", marker)),
+                format!("line 1: '{}' is a spawn marker", marker)
+            );
+        }
+    }
+}
@@ -0,0 +1,439 @@
+//! Structured maps, the same document read and written as JSON or RON
+//!
+//! Every document carries `version`. Documents from older versions still load:
+//! fields added since are optional and `upgrade` fills in anything that changed
+//! meaning. Documents from a newer version are refused instead of half read.
+
+use animation::{TileAnimation, TileFrame};
+use board::{Board, BoardLayer};
+use colors;
+use map::{Entity, Map, Spawn};
+use point::BoardPoint;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Version written by `MapDocument::from_map`
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// RGBA, 0-255 per channel
+pub type Rgba = [u8; 4];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MapDocument {
+    pub version: u32,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub author: String,
+    pub tiles_x: usize,
+    pub tiles_y: usize,
+    /// row by row, `tiles_x * tiles_y` tile ids, the walls layer
+    pub tiles: Vec<u32>,
+    /// the other board layers, left out when they are all 0
+    #[serde(default, skip_serializing_if = "LayersDocument::is_empty")]
+    pub layers: LayersDocument,
+    pub spawns: Vec<SpawnDocument>,
+    #[serde(default = "default_ceiling")]
+    pub ceiling: Rgba,
+    #[serde(default = "default_floor")]
+    pub floor: Rgba,
+    #[serde(default)]
+    pub fog: Option<Fog>,
+    /// look of each tile id, ids missing here keep their built-in look
+    #[serde(default)]
+    pub palette: Vec<TileStyle>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub entities: Vec<EntityDocument>,
+}
+
+/// Row by row like `tiles`, each layer empty or `tiles_x * tiles_y` ids
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LayersDocument {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub floor: Vec<u32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ceiling: Vec<u32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub objects: Vec<u32>,
+}
+
+impl LayersDocument {
+    pub fn is_empty(&self) -> bool {
+        self.floor.is_empty() && self.ceiling.is_empty() && self.objects.is_empty()
+    }
+
+    /// Layers of `board` that aren't all 0
+    fn from_board(board: &Board) -> LayersDocument {
+        let used = |layer: BoardLayer| {
+            let ids = board.layer(layer);
+            if ids.iter().any(|&id| id != 0) {
+                ids.to_vec()
+            } else {
+                Vec::new()
+            }
+        };
+        LayersDocument {
+            floor: used(BoardLayer::Floor),
+            ceiling: used(BoardLayer::Ceiling),
+            objects: used(BoardLayer::Objects),
+        }
+    }
+
+    /// Copies the layers given onto `board`
+    fn apply(self, board: &mut Board) -> Result<(), String> {
+        let size = board.tiles_x * board.tiles_y;
+        for (layer, ids) in [
+            (BoardLayer::Floor, self.floor),
+            (BoardLayer::Ceiling, self.ceiling),
+            (BoardLayer::Objects, self.objects),
+        ] {
+            if ids.is_empty() {
+                continue;
+            }
+            if ids.len() != size {
+                return Err(format!(
+                    "{} layer has {} tiles, expected {}x{}",
+                    layer.name(),
+                    ids.len(),
+                    board.tiles_x,
+                    board.tiles_y
+                ));
+            }
+            board.layer_mut(layer).copy_from_slice(&ids);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct SpawnDocument {
+    /// tile units, the centre of the top left tile is `0.5, 0.5`
+    pub x: f64,
+    pub y: f64,
+    /// radians, 0 faces east and positive turns towards south
+    #[serde(default)]
+    pub angle: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntityDocument {
+    pub kind: String,
+    #[serde(default)]
+    pub name: String,
+    /// tile units like `SpawnDocument`
+    pub x: f64,
+    pub y: f64,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub properties: BTreeMap<String, String>,
+}
+
+/// Walls fade into `color` between `start` and `end` tiles from the camera
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Fog {
+    pub color: Rgba,
+    pub start: f64,
+    pub end: f64,
+}
+
+impl Fog {
+    /// How much of the fog colour shows at `distance` tiles, `0.0..=1.0`
+    pub fn amount(&self, distance: f64) -> f32 {
+        if self.end <= self.start {
+            return if distance >= self.end { 1.0 } else { 0.0 };
+        }
+        ((distance - self.start) / (self.end - self.start)).clamp(0.0, 1.0) as f32
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TileStyle {
+    pub id: u32,
+    #[serde(default)]
+    pub color: Option<Rgba>,
+    /// image file drawn on the walls instead of `color`, relative to the map file
+    #[serde(default)]
+    pub texture: Option<String>,
+    /// free-form metadata such as a footstep sound, the game decides what it means
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub properties: BTreeMap<String, String>,
+}
+
+/// Everything in a map document besides the grid and the spawns, kept on the
+/// board so it can be drawn and saved again
+#[derive(Debug, Clone)]
+pub struct MapInfo {
+    pub name: String,
+    pub author: String,
+    pub ceiling: [f32; 4],
+    pub floor: [f32; 4],
+    pub fog: Option<Fog>,
+    pub palette: Vec<TileStyle>,
+}
+
+impl Default for MapInfo {
+    fn default() -> MapInfo {
+        MapInfo {
+            name: String::new(),
+            author: String::new(),
+            ceiling: colors::GRAY_CEIL,
+            floor: colors::GRAY_FLOOR,
+            fog: None,
+            palette: Vec::new(),
+        }
+    }
+}
+
+impl MapInfo {
+    /// Metadata the palette gives `tile`
+    pub fn tile_properties(&self, tile: u32) -> Option<&BTreeMap<String, String>> {
+        self.palette
+            .iter()
+            .find(|style| style.id == tile)
+            .map(|style| &style.properties)
+            .filter(|properties| !properties.is_empty())
+    }
+
+    /// Colour the palette gives `tile`, `None` when it keeps its built-in colour
+    pub fn tile_color(&self, tile: u32) -> Option<[f32; 4]> {
+        self.palette
+            .iter()
+            .find(|style| style.id == tile)
+            .and_then(|style| style.color)
+            .map(from_rgba)
+    }
+}
+
+impl MapDocument {
+    pub fn parse_json(text: &str) -> Result<MapDocument, String> {
+        let document: MapDocument = serde_json::from_str(text).map_err(|e| e.to_string())?;
+        document.upgrade()
+    }
+
+    pub fn parse_ron(text: &str) -> Result<MapDocument, String> {
+        let document: MapDocument = ron::from_str(text).map_err(|e| e.to_string())?;
+        document.upgrade()
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(self).map_err(|e| e.to_string())
+    }
+
+    pub fn to_ron(&self) -> Result<String, String> {
+        let config = ron::ser::PrettyConfig::default().compact_arrays(true);
+        ron::ser::to_string_pretty(self, config).map_err(|e| e.to_string())
+    }
+
+    /// Brings an older document up to `SCHEMA_VERSION`
+    fn upgrade(mut self) -> Result<MapDocument, String> {
+        if self.version == 0 || self.version > SCHEMA_VERSION {
+            return Err(format!(
+                "unsupported map version {}, expected 1 to {}",
+                self.version, SCHEMA_VERSION
+            ));
+        }
+        // version 2 added `layers`, which version 1 documents load without
+        self.version = SCHEMA_VERSION;
+        Ok(self)
+    }
+
+    pub fn from_map(map: &Map) -> MapDocument {
+        MapDocument::from_parts(&map.board, &map.spawns, &map.entities)
+    }
+
+    /// Document for a board kept apart from the rest of its map, like the one
+    /// being edited in the app
+    pub fn from_parts(board: &Board, spawns: &[Spawn], entities: &[Entity]) -> MapDocument {
+        let info = &board.info;
+        MapDocument {
+            version: SCHEMA_VERSION,
+            name: info.name.clone(),
+            author: info.author.clone(),
+            tiles_x: board.tiles_x,
+            tiles_y: board.tiles_y,
+            tiles: board.tiles.clone(),
+            layers: LayersDocument::from_board(board),
+            spawns: spawns
+                .iter()
+                .map(|spawn| SpawnDocument {
+                    x: spawn.position.x,
+                    y: spawn.position.y,
+                    angle: spawn.angle,
+                })
+                .collect(),
+            ceiling: to_rgba(info.ceiling),
+            floor: to_rgba(info.floor),
+            fog: info.fog,
+            palette: info.palette.clone(),
+            entities: entities
+                .iter()
+                .map(|entity| EntityDocument {
+                    kind: entity.kind.clone(),
+                    name: entity.name.clone(),
+                    x: entity.position.x,
+                    y: entity.position.y,
+                    properties: entity.properties.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Builds the board, loading palette textures relative to `base_dir`
+    pub fn into_map(self, block_size: f64, base_dir: &std::path::Path) -> Result<Map, String> {
+        if self.tiles.len() != self.tiles_x * self.tiles_y {
+            return Err(format!(
+                "{} tiles don't fill a {}x{} board",
+                self.tiles.len(),
+                self.tiles_x,
+                self.tiles_y
+            ));
+        }
+        if self.spawns.is_empty() {
+            return Err("map has no spawns".to_string());
+        }
+        let mut board = Board::new(self.tiles, self.tiles_x, self.tiles_y, block_size);
+        self.layers.apply(&mut board)?;
+        for style in self.palette.iter() {
+            if let Some(ref texture) = style.texture {
+                let path = base_dir.join(texture);
+                let image = image::open(&path)
+                    .map_err(|e| format!("could not load texture '{}': {}", path.display(), e))?
+                    .to_rgba();
+                board.animations.insert(
+                    style.id,
+                    TileAnimation {
+                        frames: vec![TileFrame::texture(image)],
+                        fps: 0.0,
+                    },
+                );
+            }
+        }
+        board.info = MapInfo {
+            name: self.name,
+            author: self.author,
+            ceiling: from_rgba(self.ceiling),
+            floor: from_rgba(self.floor),
+            fog: self.fog,
+            palette: self.palette,
+        };
+        let spawns = self
+            .spawns
+            .iter()
+            .map(|spawn| Spawn {
+                position: BoardPoint {
+                    x: spawn.x,
+                    y: spawn.y,
+                },
+                angle: spawn.angle,
+            })
+            .collect();
+        let entities = self
+            .entities
+            .into_iter()
+            .map(|entity| Entity {
+                kind: entity.kind,
+                name: entity.name,
+                position: BoardPoint {
+                    x: entity.x,
+                    y: entity.y,
+                },
+                properties: entity.properties,
+            })
+            .collect();
+        Ok(Map {
+            board,
+            spawns,
+            entities,
+        })
+    }
+}
+
+fn default_ceiling() -> Rgba {
+    to_rgba(colors::GRAY_CEIL)
+}
+
+fn default_floor() -> Rgba {
+    to_rgba(colors::GRAY_FLOOR)
+}
+
+pub fn to_rgba(color: [f32; 4]) -> Rgba {
+    let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    [
+        channel(color[0]),
+        channel(color[1]),
+        channel(color[2]),
+        channel(color[3]),
+    ]
+}
+
+pub fn from_rgba(color: Rgba) -> [f32; 4] {
+    [
+        f32::from(color[0]) / 255.0,
+        f32::from(color[1]) / 255.0,
+        f32::from(color[2]) / 255.0,
+        f32::from(color[3]) / 255.0,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use map;
+    use map::picture::Legend;
+
+    const VERSION_1: &str = r#"(
+        version: 1,
+        tiles_x: 3,
+        tiles_y: 1,
+        tiles: [1, 0, 1],
+        spawns: [(x: 1.5, y: 0.5, angle: 0.0)],
+    )"#;
+
+    #[test]
+    fn saved_maps_load_back_unchanged() {
+        let mut map = map::load("assets/maps/foggy.ron", 50.0, &Legend::default()).unwrap();
+        map.board.ceiling_tiles[11] = 23;
+        map.entities.push(Entity {
+            kind: "light".to_string(),
+            name: "lamp".to_string(),
+            position: BoardPoint { x: 2.5, y: 3.5 },
+            properties: vec![("radius".to_string(), "4".to_string())]
+                .into_iter()
+                .collect(),
+        });
+        let expected = MapDocument::from_map(&map).to_ron().unwrap();
+        for extension in ["ron", "json"].iter() {
+            let path = std::env::temp_dir().join(format!(
+                "ray-casting-round-trip-{}.{}",
+                std::process::id(),
+                extension
+            ));
+            let path = path.to_str().unwrap();
+            map::save(&map, path).unwrap();
+            let loaded = map::load(path, 50.0, &Legend::default());
+            std::fs::remove_file(path).unwrap();
+            let loaded = MapDocument::from_map(&loaded.unwrap());
+            assert_eq!(loaded.to_ron().unwrap(), expected, "through .{}", extension);
+        }
+    }
+
+    #[test]
+    fn version_1_documents_upgrade() {
+        let document = MapDocument::parse_ron(VERSION_1).unwrap();
+        assert_eq!(document.version, SCHEMA_VERSION);
+        assert!(document.layers.is_empty());
+        let map = document.into_map(50.0, std::path::Path::new("")).unwrap();
+        assert_eq!(map.board.ceiling_tiles, vec![0, 0, 0]);
+        assert_eq!(map.board.info.name, "");
+    }
+
+    #[test]
+    fn unknown_versions_are_refused() {
+        for &version in [0, SCHEMA_VERSION + 1].iter() {
+            let text = VERSION_1.replace("version: 1", &format!("version: {}", version));
+            assert_eq!(
+                MapDocument::parse_ron(&text).unwrap_err(),
+                format!("unsupported map version {}, expected 1 to 2", version)
+            );
+        }
+    }
+}
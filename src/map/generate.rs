@@ -0,0 +1,489 @@
+//! Boards generated from a seed
+//!
+//! The random numbers come from a small generator kept here rather than a
+//! library, so a seed gives the same map on every build and platform and a bug
+//! can be filed against "maze, seed 1234".
+
+use board::Board;
+use map::document::MapInfo;
+use map::{Map, Spawn};
+use point::BoardPoint;
+use std::f64::consts::{FRAC_PI_2, PI};
+
+/// Smallest board any generator accepts, in tiles
+pub const MIN_SIZE: usize = 7;
+
+const MAZE_WALL: u32 = 2;
+const DUNGEON_WALL: u32 = 1;
+const CAVE_WALL: u32 = 3;
+
+/// Rooms are never cut smaller than this, walls included
+const MIN_LEAF: usize = 6;
+/// Share of cave tiles that start out as wall
+const CAVE_FILL: f64 = 0.45;
+const CAVE_STEPS: usize = 5;
+/// Caves on small boards can fill in completely, then they are grown again
+const CAVE_ATTEMPTS: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Generator {
+    /// recursive backtracker, corridors one tile wide
+    Maze,
+    /// rooms in a binary space partition, joined by corridors
+    Dungeon,
+    /// cellular automaton caves, only the largest cave is kept
+    Caves,
+}
+
+impl Generator {
+    pub fn parse(value: &str) -> Option<Generator> {
+        match value {
+            "maze" => Some(Generator::Maze),
+            "dungeon" => Some(Generator::Dungeon),
+            "caves" => Some(Generator::Caves),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Generator::Maze => "maze",
+            Generator::Dungeon => "dungeon",
+            Generator::Caves => "caves",
+        }
+    }
+}
+
+/// SplitMix64, tiny and fully determined by its seed
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// `0..n`, `n` must not be 0
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// `low..=high`
+    fn between(&mut self, low: usize, high: usize) -> usize {
+        low + self.below(high - low + 1)
+    }
+
+    fn chance(&mut self, probability: f64) -> bool {
+        ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < probability
+    }
+}
+
+/// Tile grid being carved, everything starts as `wall`
+struct Grid {
+    tiles: Vec<u32>,
+    width: usize,
+    height: usize,
+}
+
+impl Grid {
+    fn new(width: usize, height: usize, wall: u32) -> Grid {
+        Grid {
+            tiles: vec![wall; width * height],
+            width,
+            height,
+        }
+    }
+
+    fn is_open(&self, x: usize, y: usize) -> bool {
+        self.tiles[y * self.width + x] == 0
+    }
+
+    fn set(&mut self, x: usize, y: usize, tile: u32) {
+        self.tiles[y * self.width + x] = tile;
+    }
+
+    /// Open neighbours sharing a side with `x, y`
+    fn open_neighbours(&self, x: usize, y: usize) -> Vec<(usize, usize)> {
+        let mut neighbours = Vec::with_capacity(4);
+        if x > 0 {
+            neighbours.push((x - 1, y));
+        }
+        if x + 1 < self.width {
+            neighbours.push((x + 1, y));
+        }
+        if y > 0 {
+            neighbours.push((x, y - 1));
+        }
+        if y + 1 < self.height {
+            neighbours.push((x, y + 1));
+        }
+        neighbours.retain(|&(x, y)| self.is_open(x, y));
+        neighbours
+    }
+
+    /// Open tiles connected to `x, y`
+    fn region(&self, x: usize, y: usize, seen: &mut [bool]) -> Vec<(usize, usize)> {
+        let mut region = Vec::new();
+        let mut stack = vec![(x, y)];
+        seen[y * self.width + x] = true;
+        while let Some((x, y)) = stack.pop() {
+            region.push((x, y));
+            for (nx, ny) in self.open_neighbours(x, y) {
+                if !seen[ny * self.width + nx] {
+                    seen[ny * self.width + nx] = true;
+                    stack.push((nx, ny));
+                }
+            }
+        }
+        region
+    }
+}
+
+/// A `tiles_x` by `tiles_y` board made by `generator` from `seed`, with one
+/// spawn on an open tile every other open tile can be reached from
+pub fn generate(
+    generator: Generator,
+    seed: u64,
+    tiles_x: usize,
+    tiles_y: usize,
+    block_size: f64,
+) -> Result<Map, String> {
+    if tiles_x < MIN_SIZE || tiles_y < MIN_SIZE {
+        return Err(format!(
+            "{}x{} is too small to generate, the smallest is {}x{}",
+            tiles_x, tiles_y, MIN_SIZE, MIN_SIZE
+        ));
+    }
+    let mut rng = Rng(seed);
+    let grid = match generator {
+        Generator::Maze => maze(&mut rng, tiles_x, tiles_y),
+        Generator::Dungeon => dungeon(&mut rng, tiles_x, tiles_y),
+        Generator::Caves => caves(&mut rng, tiles_x, tiles_y),
+    };
+    let spawn = place_spawn(&mut rng, &grid)
+        .ok_or_else(|| format!("{} seed {} has no open tiles", generator.name(), seed))?;
+    let mut board = Board::new(grid.tiles, tiles_x, tiles_y, block_size);
+    board.info = MapInfo {
+        name: format!("{} seed {}", generator.name(), seed),
+        ..MapInfo::default()
+    };
+    Ok(Map {
+        board,
+        spawns: vec![spawn],
+        entities: Vec::new(),
+    })
+}
+
+/// Corridors run along odd rows and columns, so an even sized board keeps an
+/// extra wall on its right or bottom edge
+fn maze(rng: &mut Rng, width: usize, height: usize) -> Grid {
+    let mut grid = Grid::new(width, height, MAZE_WALL);
+    let mut stack = vec![(1, 1)];
+    grid.set(1, 1, 0);
+    while let Some(&(x, y)) = stack.last() {
+        let mut next = Vec::with_capacity(4);
+        if x >= 3 {
+            next.push((x - 2, y));
+        }
+        if x + 3 < width {
+            next.push((x + 2, y));
+        }
+        if y >= 3 {
+            next.push((x, y - 2));
+        }
+        if y + 3 < height {
+            next.push((x, y + 2));
+        }
+        next.retain(|&(nx, ny)| !grid.is_open(nx, ny));
+        if next.is_empty() {
+            stack.pop();
+            continue;
+        }
+        let (nx, ny) = next[rng.below(next.len())];
+        grid.set((x + nx) / 2, (y + ny) / 2, 0);
+        grid.set(nx, ny, 0);
+        stack.push((nx, ny));
+    }
+    grid
+}
+
+/// Area of the board a room is placed in, `x, y, width, height`
+type Leaf = (usize, usize, usize, usize);
+
+fn dungeon(rng: &mut Rng, width: usize, height: usize) -> Grid {
+    let mut grid = Grid::new(width, height, DUNGEON_WALL);
+    // the outer wall is left alone
+    split(rng, &mut grid, (1, 1, width - 2, height - 2));
+    grid
+}
+
+/// Carves a room in every leaf under `leaf` and joins siblings, returning a
+/// tile inside one of the rooms
+fn split(rng: &mut Rng, grid: &mut Grid, leaf: Leaf) -> (usize, usize) {
+    let (x, y, width, height) = leaf;
+    let can_split_x = width >= MIN_LEAF * 2;
+    let can_split_y = height >= MIN_LEAF * 2;
+    let split_x = match (can_split_x, can_split_y) {
+        (false, false) => return room(rng, grid, leaf),
+        (true, false) => true,
+        (false, true) => false,
+        (true, true) => {
+            if width > height {
+                true
+            } else if height > width {
+                false
+            } else {
+                rng.chance(0.5)
+            }
+        }
+    };
+    let (first, second) = if split_x {
+        let cut = rng.between(MIN_LEAF, width - MIN_LEAF);
+        ((x, y, cut, height), (x + cut, y, width - cut, height))
+    } else {
+        let cut = rng.between(MIN_LEAF, height - MIN_LEAF);
+        ((x, y, width, cut), (x, y + cut, width, height - cut))
+    };
+    let a = split(rng, grid, first);
+    let b = split(rng, grid, second);
+    corridor(rng, grid, a, b);
+    if rng.chance(0.5) {
+        a
+    } else {
+        b
+    }
+}
+
+/// Carves a room of random size inside `leaf`, keeping a wall on every side
+fn room(rng: &mut Rng, grid: &mut Grid, leaf: Leaf) -> (usize, usize) {
+    let (x, y, width, height) = leaf;
+    let room_width = rng.between((width - 2).min(3), width - 2);
+    let room_height = rng.between((height - 2).min(3), height - 2);
+    let left = x + 1 + rng.below(width - 1 - room_width);
+    let top = y + 1 + rng.below(height - 1 - room_height);
+    for ty in top..top + room_height {
+        for tx in left..left + room_width {
+            grid.set(tx, ty, 0);
+        }
+    }
+    (left + room_width / 2, top + room_height / 2)
+}
+
+/// L shaped corridor between two open tiles
+fn corridor(rng: &mut Rng, grid: &mut Grid, a: (usize, usize), b: (usize, usize)) {
+    let corner = if rng.chance(0.5) {
+        (b.0, a.1)
+    } else {
+        (a.0, b.1)
+    };
+    for &(from, to) in [(a, corner), (corner, b)].iter() {
+        for x in from.0.min(to.0)..=from.0.max(to.0) {
+            for y in from.1.min(to.1)..=from.1.max(to.1) {
+                grid.set(x, y, 0);
+            }
+        }
+    }
+}
+
+fn caves(rng: &mut Rng, width: usize, height: usize) -> Grid {
+    let mut grid = cave(rng, width, height);
+    for _ in 1..CAVE_ATTEMPTS {
+        if grid.tiles.contains(&0) {
+            break;
+        }
+        grid = cave(rng, width, height);
+    }
+    grid
+}
+
+fn cave(rng: &mut Rng, width: usize, height: usize) -> Grid {
+    let border = |x: usize, y: usize| x == 0 || y == 0 || x == width - 1 || y == height - 1;
+    let mut grid = Grid::new(width, height, CAVE_WALL);
+    for y in 0..height {
+        for x in 0..width {
+            if !border(x, y) && !rng.chance(CAVE_FILL) {
+                grid.set(x, y, 0);
+            }
+        }
+    }
+    for _ in 0..CAVE_STEPS {
+        let mut next = Grid::new(width, height, CAVE_WALL);
+        for y in 1..height - 1 {
+            for x in 1..width - 1 {
+                let walls = (y - 1..=y + 1)
+                    .flat_map(|ny| (x - 1..=x + 1).map(move |nx| (nx, ny)))
+                    .filter(|&(nx, ny)| (nx, ny) != (x, y) && !grid.is_open(nx, ny))
+                    .count();
+                // a wall with 4 or more wall neighbours stays, floor needs 5 to fill in
+                let wall = if grid.is_open(x, y) {
+                    walls >= 5
+                } else {
+                    walls >= 4
+                };
+                if !wall {
+                    next.set(x, y, 0);
+                }
+            }
+        }
+        grid = next;
+    }
+
+    // every cave but the largest is filled in, so the spawn can reach all floor
+    let mut seen = vec![false; width * height];
+    let mut regions = Vec::new();
+    for y in 0..height {
+        for x in 0..width {
+            if grid.is_open(x, y) && !seen[y * width + x] {
+                regions.push(grid.region(x, y, &mut seen));
+            }
+        }
+    }
+    regions.sort_by_key(|region| std::cmp::Reverse(region.len()));
+    for region in regions.iter().skip(1) {
+        for &(x, y) in region.iter() {
+            grid.set(x, y, CAVE_WALL);
+        }
+    }
+    grid
+}
+
+/// A random open tile, facing one of its open neighbours when it has any
+fn place_spawn(rng: &mut Rng, grid: &Grid) -> Option<Spawn> {
+    let open = (0..grid.height)
+        .flat_map(|y| (0..grid.width).map(move |x| (x, y)))
+        .filter(|&(x, y)| grid.is_open(x, y))
+        .collect::<Vec<(usize, usize)>>();
+    if open.is_empty() {
+        return None;
+    }
+    let (x, y) = open[rng.below(open.len())];
+    let neighbours = grid.open_neighbours(x, y);
+    let angle = match neighbours.first() {
+        Some(&(nx, _)) if nx > x => 0.0,
+        Some(&(_, ny)) if ny > y => FRAC_PI_2,
+        Some(&(nx, _)) if nx < x => PI,
+        Some(_) => -FRAC_PI_2,
+        None => 0.0,
+    };
+    Some(Spawn {
+        position: BoardPoint {
+            x: x as f64 + 0.5,
+            y: y as f64 + 0.5,
+        },
+        angle,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEED: u64 = 1234;
+
+    /// `#` for walls and `.` for open tiles, one string per row
+    fn rows(map: &Map) -> Vec<String> {
+        map.board
+            .tiles
+            .chunks(map.board.tiles_x)
+            .map(|row| {
+                row.iter()
+                    .map(|&tile| if tile == 0 { '.' } else { '#' })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn spawn_tile(map: &Map) -> (usize, usize) {
+        let position = map.spawns[0].position;
+        (position.x as usize, position.y as usize)
+    }
+
+    #[test]
+    fn maze_seed_gives_the_same_map() {
+        let map = generate(Generator::Maze, SEED, 9, 9, 50.0).unwrap();
+        assert_eq!(
+            rows(&map),
+            [
+                "#########",
+                "#.#...#.#",
+                "#.#.#.#.#",
+                "#...#.#.#",
+                "#####.#.#",
+                "#...#...#",
+                "#.#.###.#",
+                "#.#.....#",
+                "#########",
+            ]
+        );
+        assert_eq!(spawn_tile(&map), (5, 2));
+    }
+
+    #[test]
+    fn dungeon_seed_gives_the_same_map() {
+        let map = generate(Generator::Dungeon, SEED, 14, 9, 50.0).unwrap();
+        assert_eq!(
+            rows(&map),
+            [
+                "##############",
+                "##############",
+                "##############",
+                "###...##....##",
+                "###...##....##",
+                "###.........##",
+                "###...##....##",
+                "##############",
+                "##############",
+            ]
+        );
+        assert_eq!(spawn_tile(&map), (3, 6));
+    }
+
+    #[test]
+    fn caves_seed_gives_the_same_map() {
+        let map = generate(Generator::Caves, SEED, 12, 9, 50.0).unwrap();
+        assert_eq!(
+            rows(&map),
+            [
+                "############",
+                "######..####",
+                "#####....###",
+                "#####.....##",
+                "#####.....##",
+                "######...###",
+                "############",
+                "############",
+                "############",
+            ]
+        );
+        assert_eq!(spawn_tile(&map), (7, 1));
+    }
+
+    #[test]
+    fn spawn_reaches_every_open_tile() {
+        for &generator in [Generator::Maze, Generator::Dungeon, Generator::Caves].iter() {
+            for seed in 0..20 {
+                let map = generate(generator, seed, 24, 17, 50.0).unwrap();
+                let grid = Grid {
+                    tiles: map.board.tiles.clone(),
+                    width: map.board.tiles_x,
+                    height: map.board.tiles_y,
+                };
+                let (x, y) = spawn_tile(&map);
+                assert!(grid.is_open(x, y), "{} seed {}", generator.name(), seed);
+                let mut seen = vec![false; grid.tiles.len()];
+                let reached = grid.region(x, y, &mut seen).len();
+                let open = grid.tiles.iter().filter(|&&tile| tile == 0).count();
+                assert_eq!(reached, open, "{} seed {}", generator.name(), seed);
+            }
+        }
+    }
+
+    #[test]
+    fn boards_below_the_minimum_size_are_refused() {
+        assert!(generate(Generator::Maze, SEED, MIN_SIZE - 1, MIN_SIZE, 50.0).is_err());
+        assert!(generate(Generator::Caves, SEED, MIN_SIZE, MIN_SIZE - 1, 50.0).is_err());
+    }
+}
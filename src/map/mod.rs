@@ -0,0 +1,102 @@
+use board::Board;
+use point::BoardPoint;
+
+pub mod ascii;
+pub mod document;
+pub mod generate;
+pub mod picture;
+pub mod tiled;
+pub mod validate;
+
+use self::document::MapDocument;
+use self::picture::Legend;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Where a player starts and the way they face
+#[derive(Debug, Clone, Copy)]
+pub struct Spawn {
+    pub position: BoardPoint,
+    pub angle: f64,
+}
+
+/// Anything placed on a map that isn't a tile or a spawn, such as a light or
+/// a pickup, kept for whatever wants to act on it
+#[derive(Debug, Clone)]
+pub struct Entity {
+    pub kind: String,
+    pub name: String,
+    /// tile units like `Spawn::position`
+    pub position: BoardPoint,
+    pub properties: BTreeMap<String, String>,
+}
+
+/// A board read from a map file, with its spawn points in file order
+pub struct Map {
+    pub board: Board,
+    pub spawns: Vec<Spawn>,
+    pub entities: Vec<Entity>,
+}
+
+impl Map {
+    /// Spawn for player `i`, reusing the map's spawns when there are more players
+    pub fn spawn(&self, i: usize) -> Spawn {
+        self.spawns[i % self.spawns.len()]
+    }
+}
+
+/// Reads the map at `path`, with `block_size` pixels per tile on the 2d map.
+/// `.json` and `.ron` files are map documents, unless the JSON is a Tiled map,
+/// `.tmj` and `.tmx` files are Tiled maps, `.png` files are read through
+/// `legend` and anything else is a text map.
+pub fn load(path: &str, block_size: f64, legend: &Legend) -> Result<Map, String> {
+    if extension(path) == "png" {
+        let image = image::open(path)
+            .map_err(|e| format!("could not read '{}': {}", path, e))?
+            .to_rgba();
+        return picture::parse(&image, legend, block_size).map_err(|e| format!("{}: {}", path, e));
+    }
+    let text =
+        std::fs::read_to_string(path).map_err(|e| format!("could not read '{}': {}", path, e))?;
+    let base_dir = Path::new(path).parent().unwrap_or_else(|| Path::new(""));
+    let map = match extension(path).as_str() {
+        "json" if is_tiled_json(&text) => tiled::parse_json(&text, block_size, base_dir),
+        "json" => MapDocument::parse_json(&text)
+            .and_then(|document| document.into_map(block_size, base_dir)),
+        "tmj" => tiled::parse_json(&text, block_size, base_dir),
+        "tmx" => tiled::parse_tmx(&text, block_size, base_dir),
+        "ron" => MapDocument::parse_ron(&text)
+            .and_then(|document| document.into_map(block_size, base_dir)),
+        _ => ascii::parse(&text, block_size),
+    };
+    map.map_err(|e| format!("{}: {}", path, e))
+}
+
+/// Writes `map` as a `.json` or `.ron` map document
+pub fn save(map: &Map, path: &str) -> Result<(), String> {
+    save_document(&MapDocument::from_map(map), path)
+}
+
+pub fn save_document(document: &MapDocument, path: &str) -> Result<(), String> {
+    let text = match extension(path).as_str() {
+        "json" => document.to_json(),
+        "ron" => document.to_ron(),
+        _ => Err("maps are saved as .json or .ron".to_string()),
+    }
+    .map_err(|e| format!("{}: {}", path, e))?;
+    std::fs::write(path, text).map_err(|e| format!("could not write '{}': {}", path, e))
+}
+
+/// Tiled writes a `tiledversion`, map documents never do
+fn is_tiled_json(text: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(text)
+        .map(|value| value.get("tiledversion").is_some())
+        .unwrap_or(false)
+}
+
+fn extension(path: &str) -> String {
+    Path::new(path)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
@@ -0,0 +1,236 @@
+//! Maps drawn as images, one pixel per tile
+//!
+//! Each pixel's colour is looked up in a `Legend`, which gives either a tile id
+//! or a spawn. Fully transparent pixels are empty floor. The image size is the
+//! board size.
+//!
+//! The built-in legend reads white as floor, black as tile 1, pure red, blue,
+//! green and orange as the tiles they are drawn with, and yellow as a spawn
+//! facing east. A legend file adds to it, later lines winning:
+//!
+//! ```text
+//! # comment
+//! tile #808080 2
+//! spawn #ff00ff north
+//! spawn #00ffff 45
+//! ```
+//!
+//! Spawns face `north`, `east`, `south`, `west` or an angle in degrees.
+
+use board::Board;
+use image::RgbaImage;
+use map::{Map, Spawn};
+use point::BoardPoint;
+
+/// What a pixel colour stands for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LegendEntry {
+    Tile(u32),
+    /// a spawn facing `angle` radians, on empty floor
+    Spawn(f64),
+}
+
+#[derive(Debug, Clone)]
+pub struct Legend {
+    entries: Vec<([u8; 3], LegendEntry)>,
+}
+
+impl Default for Legend {
+    fn default() -> Legend {
+        Legend {
+            entries: vec![
+                ([255, 255, 255], LegendEntry::Tile(0)),
+                ([0, 0, 0], LegendEntry::Tile(1)),
+                ([255, 0, 0], LegendEntry::Tile(1)),
+                ([0, 0, 255], LegendEntry::Tile(2)),
+                ([0, 255, 0], LegendEntry::Tile(3)),
+                ([255, 165, 0], LegendEntry::Tile(4)),
+                ([255, 255, 0], LegendEntry::Spawn(0.0)),
+            ],
+        }
+    }
+}
+
+impl Legend {
+    /// The built-in legend plus the lines of a legend file, errors name the
+    /// 1-based line they were found on
+    pub fn parse(text: &str) -> Result<Legend, String> {
+        let mut legend = Legend::default();
+        for (i, line) in text.lines().enumerate() {
+            let number = i + 1;
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            let words: Vec<&str> = trimmed.split_whitespace().collect();
+            let entry = match words.as_slice() {
+                ["tile", color, tile] => {
+                    let tile = tile
+                        .parse::<u32>()
+                        .map_err(|_| format!("line {}: bad tile id '{}'", number, tile))?;
+                    (color, LegendEntry::Tile(tile))
+                }
+                ["spawn", color, facing] => {
+                    let angle = parse_facing(facing).ok_or_else(|| {
+                        format!(
+                            "line {}: bad facing '{}', expected north, east, south, west or degrees",
+                            number, facing
+                        )
+                    })?;
+                    (color, LegendEntry::Spawn(angle))
+                }
+                _ => {
+                    return Err(format!(
+                    "line {}: expected 'tile #RRGGBB TILE' or 'spawn #RRGGBB FACING', found '{}'",
+                    number, trimmed
+                ))
+                }
+            };
+            let color = parse_color(entry.0)
+                .ok_or_else(|| format!("line {}: bad colour '{}'", number, entry.0))?;
+            legend.entries.push((color, entry.1));
+        }
+        Ok(legend)
+    }
+
+    pub fn get(&self, color: [u8; 3]) -> Option<LegendEntry> {
+        self.entries
+            .iter()
+            .rev()
+            .find(|&&(entry_color, _)| entry_color == color)
+            .map(|&(_, entry)| entry)
+    }
+}
+
+fn parse_color(value: &str) -> Option<[u8; 3]> {
+    let hex = value.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| {
+        hex.get(i..i + 2)
+            .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+    };
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+fn parse_facing(value: &str) -> Option<f64> {
+    let degrees = match value {
+        "north" => -90.0,
+        "east" => 0.0,
+        "south" => 90.0,
+        "west" => 180.0,
+        _ => value.parse::<f64>().ok()?,
+    };
+    Some(f64::to_radians(degrees))
+}
+
+/// Builds a map from `image`, one tile per pixel
+pub fn parse(image: &RgbaImage, legend: &Legend, block_size: f64) -> Result<Map, String> {
+    let mut tiles = Vec::with_capacity((image.width() * image.height()) as usize);
+    let mut spawns = Vec::new();
+    for (x, y, pixel) in image.enumerate_pixels() {
+        let [r, g, b, a] = pixel.0;
+        if a == 0 {
+            tiles.push(0);
+            continue;
+        }
+        match legend.get([r, g, b]) {
+            Some(LegendEntry::Tile(tile)) => tiles.push(tile),
+            Some(LegendEntry::Spawn(angle)) => {
+                spawns.push(Spawn {
+                    position: BoardPoint {
+                        x: f64::from(x) + 0.5,
+                        y: f64::from(y) + 0.5,
+                    },
+                    angle,
+                });
+                tiles.push(0);
+            }
+            None => {
+                return Err(format!(
+                    "pixel {}, {}: colour #{:02x}{:02x}{:02x} isn't in the legend",
+                    x, y, r, g, b
+                ))
+            }
+        }
+    }
+    if spawns.is_empty() {
+        return Err("no spawn pixel, the built-in legend uses yellow #ffff00".to_string());
+    }
+    Ok(Map {
+        board: Board::new(
+            tiles,
+            image.width() as usize,
+            image.height() as usize,
+            block_size,
+        ),
+        spawns,
+        entities: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+    use std::f64::consts::PI;
+
+    /// 3x1 image of the given pixels
+    fn row(pixels: [[u8; 4]; 3]) -> RgbaImage {
+        RgbaImage::from_fn(3, 1, |x, _| Rgba(pixels[x as usize]))
+    }
+
+    #[test]
+    fn transparent_pixels_are_floor_whatever_their_colour() {
+        let image = row([[0, 0, 0, 255], [200, 10, 10, 0], [255, 255, 0, 255]]);
+        let map = parse(&image, &Legend::default(), 50.0).unwrap();
+        assert_eq!(map.board.tiles, vec![1, 0, 0]);
+        assert_eq!(map.spawns.len(), 1);
+        assert_eq!(map.spawns[0].position.x, 2.5);
+    }
+
+    #[test]
+    fn colours_missing_from_the_legend_are_errors() {
+        let image = row([[0, 0, 0, 255], [18, 52, 86, 255], [255, 255, 0, 255]]);
+        assert_eq!(
+            parse(&image, &Legend::default(), 50.0).err(),
+            Some("pixel 1, 0: colour #123456 isn't in the legend".to_string())
+        );
+    }
+
+    #[test]
+    fn later_legend_lines_win() {
+        let legend = Legend::parse(
+            "# grey walls\ntile #808080 2\ntile #808080 7\ntile #000000 9\nspawn #ff00ff west\n",
+        )
+        .unwrap();
+        assert_eq!(legend.get([128, 128, 128]), Some(LegendEntry::Tile(7)));
+        // lines replace the built-in entries too
+        assert_eq!(legend.get([0, 0, 0]), Some(LegendEntry::Tile(9)));
+        assert_eq!(legend.get([255, 0, 255]), Some(LegendEntry::Spawn(PI)));
+        assert_eq!(legend.get([0, 0, 255]), Some(LegendEntry::Tile(2)));
+    }
+
+    #[test]
+    fn bad_colours_and_facings_name_their_line() {
+        let cases = [
+            ("tile 808080 2", "line 1: bad colour '808080'"),
+            ("tile #80808 2", "line 1: bad colour '#80808'"),
+            ("\ntile #80808g 2", "line 2: bad colour '#80808g'"),
+            ("tile #808080 two", "line 1: bad tile id 'two'"),
+            (
+                "spawn #ff00ff up",
+                "line 1: bad facing 'up', expected north, east, south, west or degrees",
+            ),
+        ];
+        for &(text, message) in cases.iter() {
+            assert_eq!(Legend::parse(text).err(), Some(message.to_string()));
+        }
+        let legend = Legend::parse("spawn #ff00ff 45").unwrap();
+        assert_eq!(
+            legend.get([255, 0, 255]),
+            Some(LegendEntry::Spawn(45f64.to_radians()))
+        );
+    }
+}
@@ -0,0 +1,660 @@
+//! Imports maps made in the Tiled editor, saved as JSON (`.tmj`, `.json`) or TMX
+//!
+//! - The tile layer named `walls`, or else the first tile layer not named after
+//!   another board layer, becomes the walls. Tile layers named `floor`, `ceiling`
+//!   and `objects` become those board layers. Tile layers must use the CSV or
+//!   plain array format, not base64.
+//! - A tile's id on the board is its global id, unless its tileset gives it an
+//!   integer `tile_id` property. Empty cells are 0.
+//! - The other custom properties of a tileset tile become that tile id's metadata.
+//!   A `color` property also sets its colour.
+//! - Objects whose class (or type) is `spawn` are spawns. They face along their
+//!   `angle` property, in degrees, or else their rotation. All other objects
+//!   become entities.
+//!
+//! `assets/maps/hall.tmj` and `assets/maps/hall.tmx` are the same sample map in
+//! both formats, each using an external tileset.
+
+use board::{Board, BoardLayer};
+use map::document::{MapInfo, TileStyle};
+use map::{Entity, Map, Spawn};
+use point::BoardPoint;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Tiled keeps flip and rotation flags in the top bits of a global id
+const GID_FLAGS: u32 = 0xF000_0000;
+
+/// Tiled map reduced to what the importer reads, shared by JSON and TMX
+struct TiledMap {
+    orientation: String,
+    width: usize,
+    height: usize,
+    tile_width: f64,
+    tile_height: f64,
+    tile_layers: Vec<TileLayer>,
+    objects: Vec<TiledObject>,
+    tilesets: Vec<Tileset>,
+}
+
+struct TileLayer {
+    name: String,
+    gids: Vec<u32>,
+}
+
+struct TiledObject {
+    name: String,
+    class: String,
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+    rotation: f64,
+    properties: BTreeMap<String, String>,
+}
+
+/// Local tile id and its custom properties
+type TilesetTile = (u32, BTreeMap<String, String>);
+
+struct Tileset {
+    first_gid: u32,
+    tiles: Vec<TilesetTile>,
+}
+
+/// Reads a Tiled JSON map, external tilesets are looked up next to it
+pub fn parse_json(text: &str, block_size: f64, base_dir: &Path) -> Result<Map, String> {
+    let json: JsonMap = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    json_map(json, base_dir)?.into_map(block_size)
+}
+
+/// Reads a Tiled TMX map, external tilesets are looked up next to it
+pub fn parse_tmx(text: &str, block_size: f64, base_dir: &Path) -> Result<Map, String> {
+    let document = roxmltree::Document::parse(text).map_err(|e| e.to_string())?;
+    tmx_map(document.root_element(), base_dir)?.into_map(block_size)
+}
+
+impl TiledMap {
+    fn into_map(self, block_size: f64) -> Result<Map, String> {
+        if self.orientation != "orthogonal" {
+            return Err(format!(
+                "{} maps aren't supported, only orthogonal ones",
+                self.orientation
+            ));
+        }
+        if !(self.tile_width > 0.0 && self.tile_height > 0.0) {
+            return Err(format!(
+                "tiles are {}x{}, expected a width and height above 0",
+                self.tile_width, self.tile_height
+            ));
+        }
+        let board_layer = |layer: &TileLayer| BoardLayer::parse(&layer.name);
+        let walls = self
+            .tile_layers
+            .iter()
+            .find(|layer| board_layer(layer) == Some(BoardLayer::Walls))
+            .or_else(|| {
+                self.tile_layers
+                    .iter()
+                    .find(|layer| board_layer(layer).is_none())
+            })
+            .ok_or_else(|| "no tile layer".to_string())?;
+        let tiles = self.layer_ids(walls)?;
+
+        let mut spawns = Vec::new();
+        let mut entities = Vec::new();
+        for object in self.objects.iter() {
+            // rectangles stand for their middle, points have no size
+            let position = BoardPoint {
+                x: (object.x + object.width / 2.0) / self.tile_width,
+                y: (object.y + object.height / 2.0) / self.tile_height,
+            };
+            if object.class.eq_ignore_ascii_case("spawn") {
+                let degrees = match object.properties.get("angle") {
+                    Some(angle) => angle
+                        .parse::<f64>()
+                        .map_err(|_| format!("spawn '{}' has a bad angle", object.name))?,
+                    None => object.rotation,
+                };
+                spawns.push(Spawn {
+                    position,
+                    angle: degrees.to_radians(),
+                });
+            } else {
+                entities.push(Entity {
+                    kind: object.class.clone(),
+                    name: object.name.clone(),
+                    position,
+                    properties: object.properties.clone(),
+                });
+            }
+        }
+        if spawns.is_empty() {
+            return Err("no spawn, add an object with the class 'spawn'".to_string());
+        }
+
+        let mut palette = Vec::new();
+        for tileset in self.tilesets.iter() {
+            for (local_id, properties) in tileset.tiles.iter() {
+                let id = self.tile_id(tileset.first_gid + local_id)?;
+                let mut properties = properties.clone();
+                properties.remove("tile_id");
+                let color = match properties.remove("color") {
+                    Some(color) => Some(parse_color(&color)?),
+                    None => None,
+                };
+                if color.is_some() || !properties.is_empty() {
+                    palette.push(TileStyle {
+                        id,
+                        color,
+                        texture: None,
+                        properties,
+                    });
+                }
+            }
+        }
+
+        let mut board = Board::new(tiles, self.width, self.height, block_size);
+        for layer in self.tile_layers.iter() {
+            match board_layer(layer) {
+                Some(BoardLayer::Walls) | None => {}
+                Some(kind) => {
+                    let ids = self.layer_ids(layer)?;
+                    board.layer_mut(kind).copy_from_slice(&ids);
+                }
+            }
+        }
+        board.info = MapInfo {
+            palette,
+            ..MapInfo::default()
+        };
+        Ok(Map {
+            board,
+            spawns,
+            entities,
+        })
+    }
+
+    /// Board ids of every cell in `layer`, which must cover the whole map
+    fn layer_ids(&self, layer: &TileLayer) -> Result<Vec<u32>, String> {
+        if layer.gids.len() != self.width * self.height {
+            return Err(format!(
+                "layer '{}' has {} tiles, expected {}x{}",
+                layer.name,
+                layer.gids.len(),
+                self.width,
+                self.height
+            ));
+        }
+        layer.gids.iter().map(|&gid| self.tile_id(gid)).collect()
+    }
+
+    fn tile_id(&self, gid: u32) -> Result<u32, String> {
+        let gid = gid & !GID_FLAGS;
+        if gid == 0 {
+            return Ok(0);
+        }
+        let tileset = self
+            .tilesets
+            .iter()
+            .filter(|tileset| tileset.first_gid <= gid)
+            .max_by_key(|tileset| tileset.first_gid);
+        let properties = tileset.and_then(|tileset| {
+            let local_id = gid - tileset.first_gid;
+            tileset
+                .tiles
+                .iter()
+                .find(|(id, _)| *id == local_id)
+                .map(|(_, properties)| properties)
+        });
+        match properties.and_then(|properties| properties.get("tile_id")) {
+            Some(tile_id) => tile_id
+                .parse::<u32>()
+                .map_err(|_| format!("bad tile_id '{}' on tile {}", tile_id, gid)),
+            None => Ok(gid),
+        }
+    }
+}
+
+/// `#rrggbb` or Tiled's `#aarrggbb`
+fn parse_color(value: &str) -> Result<[u8; 4], String> {
+    let hex = value.trim_start_matches('#');
+    let channel = |i: usize| {
+        hex.get(i..i + 2)
+            .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+    };
+    let color = match hex.len() {
+        6 => channel(0).and_then(|r| Some([r, channel(2)?, channel(4)?, 255])),
+        8 => channel(2).and_then(|r| Some([r, channel(4)?, channel(6)?, channel(0)?])),
+        _ => None,
+    };
+    color.ok_or_else(|| format!("bad colour '{}'", value))
+}
+
+#[derive(Deserialize)]
+struct JsonMap {
+    #[serde(default = "default_orientation")]
+    orientation: String,
+    width: usize,
+    height: usize,
+    tilewidth: f64,
+    tileheight: f64,
+    #[serde(default)]
+    infinite: bool,
+    layers: Vec<JsonLayer>,
+    #[serde(default)]
+    tilesets: Vec<JsonTileset>,
+}
+
+#[derive(Deserialize)]
+struct JsonLayer {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    data: serde_json::Value,
+    #[serde(default)]
+    objects: Vec<JsonObject>,
+    /// children of a group layer
+    #[serde(default)]
+    layers: Vec<JsonLayer>,
+}
+
+#[derive(Deserialize)]
+struct JsonObject {
+    #[serde(default)]
+    name: String,
+    /// called `type` before Tiled 1.9
+    #[serde(default, rename = "type")]
+    kind: String,
+    #[serde(default)]
+    class: String,
+    x: f64,
+    y: f64,
+    #[serde(default)]
+    width: f64,
+    #[serde(default)]
+    height: f64,
+    #[serde(default)]
+    rotation: f64,
+    #[serde(default)]
+    properties: Vec<JsonProperty>,
+}
+
+#[derive(Deserialize)]
+struct JsonProperty {
+    name: String,
+    value: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+struct JsonTileset {
+    firstgid: u32,
+    #[serde(default)]
+    source: Option<String>,
+    #[serde(default)]
+    tiles: Vec<JsonTile>,
+}
+
+/// External JSON tileset file, the map that uses it gives its `firstgid`
+#[derive(Deserialize)]
+struct JsonTilesetFile {
+    #[serde(default)]
+    tiles: Vec<JsonTile>,
+}
+
+#[derive(Deserialize)]
+struct JsonTile {
+    id: u32,
+    #[serde(default)]
+    properties: Vec<JsonProperty>,
+}
+
+fn default_orientation() -> String {
+    "orthogonal".to_string()
+}
+
+fn json_properties(properties: &[JsonProperty]) -> BTreeMap<String, String> {
+    properties
+        .iter()
+        .map(|property| {
+            let value = match property.value {
+                serde_json::Value::String(ref value) => value.clone(),
+                ref value => value.to_string(),
+            };
+            (property.name.clone(), value)
+        })
+        .collect()
+}
+
+fn json_map(json: JsonMap, base_dir: &Path) -> Result<TiledMap, String> {
+    if json.infinite {
+        return Err("infinite maps aren't supported".to_string());
+    }
+    let mut map = TiledMap {
+        orientation: json.orientation,
+        width: json.width,
+        height: json.height,
+        tile_width: json.tilewidth,
+        tile_height: json.tileheight,
+        tile_layers: Vec::new(),
+        objects: Vec::new(),
+        tilesets: Vec::new(),
+    };
+    json_layers(&json.layers, &mut map)?;
+    for tileset in json.tilesets.iter() {
+        let tiles = match tileset.source {
+            Some(ref source) => external_tileset(&base_dir.join(source))?,
+            None => tileset
+                .tiles
+                .iter()
+                .map(|tile| (tile.id, json_properties(&tile.properties)))
+                .collect(),
+        };
+        map.tilesets.push(Tileset {
+            first_gid: tileset.firstgid,
+            tiles,
+        });
+    }
+    Ok(map)
+}
+
+fn json_layers(layers: &[JsonLayer], map: &mut TiledMap) -> Result<(), String> {
+    for layer in layers.iter() {
+        match layer.kind.as_str() {
+            "tilelayer" => {
+                let gids = match layer.data {
+                    serde_json::Value::Array(ref values) => values
+                        .iter()
+                        .map(|value| value.as_u64().map(|gid| gid as u32))
+                        .collect::<Option<Vec<u32>>>(),
+                    _ => None,
+                }
+                .ok_or_else(|| {
+                    format!(
+                        "layer '{}' isn't stored as CSV, change its tile layer format",
+                        layer.name
+                    )
+                })?;
+                map.tile_layers.push(TileLayer {
+                    name: layer.name.clone(),
+                    gids,
+                });
+            }
+            "objectgroup" => {
+                for object in layer.objects.iter() {
+                    let class = if object.class.is_empty() {
+                        &object.kind
+                    } else {
+                        &object.class
+                    };
+                    map.objects.push(TiledObject {
+                        name: object.name.clone(),
+                        class: class.clone(),
+                        x: object.x,
+                        y: object.y,
+                        width: object.width,
+                        height: object.height,
+                        rotation: object.rotation,
+                        properties: json_properties(&object.properties),
+                    });
+                }
+            }
+            "group" => json_layers(&layer.layers, map)?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Tiles of a `.tsx` or JSON tileset file
+fn external_tileset(path: &Path) -> Result<Vec<TilesetTile>, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("could not read tileset '{}': {}", path.display(), e))?;
+    let in_file = |e: String| format!("{}: {}", path.display(), e);
+    if path.extension().is_some_and(|extension| extension == "tsx") {
+        let document = roxmltree::Document::parse(&text).map_err(|e| in_file(e.to_string()))?;
+        tmx_tiles(document.root_element()).map_err(in_file)
+    } else {
+        let tileset: JsonTilesetFile =
+            serde_json::from_str(&text).map_err(|e| in_file(e.to_string()))?;
+        Ok(tileset
+            .tiles
+            .iter()
+            .map(|tile| (tile.id, json_properties(&tile.properties)))
+            .collect())
+    }
+}
+
+fn attribute<T: std::str::FromStr>(node: roxmltree::Node, name: &str) -> Result<T, String> {
+    let value = node
+        .attribute(name)
+        .ok_or_else(|| format!("<{}> has no '{}'", node.tag_name().name(), name))?;
+    value
+        .parse::<T>()
+        .map_err(|_| format!("<{}> has a bad '{}'", node.tag_name().name(), name))
+}
+
+fn optional_attribute(node: roxmltree::Node, name: &str) -> Result<f64, String> {
+    match node.attribute(name) {
+        Some(_) => attribute(node, name),
+        None => Ok(0.0),
+    }
+}
+
+fn children<'a, 'input>(
+    node: roxmltree::Node<'a, 'input>,
+    name: &'static str,
+) -> impl Iterator<Item = roxmltree::Node<'a, 'input>> {
+    node.children()
+        .filter(move |child| child.is_element() && child.has_tag_name(name))
+}
+
+fn tmx_properties(node: roxmltree::Node) -> BTreeMap<String, String> {
+    children(node, "properties")
+        .flat_map(|properties| children(properties, "property"))
+        .filter_map(|property| {
+            let name = property.attribute("name")?.to_string();
+            // multi-line strings are kept as the element text instead
+            let value = property
+                .attribute("value")
+                .or_else(|| property.text())
+                .unwrap_or("")
+                .to_string();
+            Some((name, value))
+        })
+        .collect()
+}
+
+fn tmx_tiles(tileset: roxmltree::Node) -> Result<Vec<TilesetTile>, String> {
+    children(tileset, "tile")
+        .map(|tile| Ok((attribute(tile, "id")?, tmx_properties(tile))))
+        .collect()
+}
+
+fn tmx_map(root: roxmltree::Node, base_dir: &Path) -> Result<TiledMap, String> {
+    if root.attribute("infinite") == Some("1") {
+        return Err("infinite maps aren't supported".to_string());
+    }
+    let mut map = TiledMap {
+        orientation: root
+            .attribute("orientation")
+            .map_or_else(default_orientation, str::to_string),
+        width: attribute(root, "width")?,
+        height: attribute(root, "height")?,
+        tile_width: attribute(root, "tilewidth")?,
+        tile_height: attribute(root, "tileheight")?,
+        tile_layers: Vec::new(),
+        objects: Vec::new(),
+        tilesets: Vec::new(),
+    };
+    for tileset in children(root, "tileset") {
+        let tiles = match tileset.attribute("source") {
+            Some(source) => external_tileset(&base_dir.join(source))?,
+            None => tmx_tiles(tileset)?,
+        };
+        map.tilesets.push(Tileset {
+            first_gid: attribute(tileset, "firstgid")?,
+            tiles,
+        });
+    }
+    tmx_layers(root, &mut map)?;
+    Ok(map)
+}
+
+fn tmx_layers(parent: roxmltree::Node, map: &mut TiledMap) -> Result<(), String> {
+    for node in parent.children().filter(|node| node.is_element()) {
+        match node.tag_name().name() {
+            "layer" => {
+                let name = node.attribute("name").unwrap_or("").to_string();
+                let data = children(node, "data")
+                    .next()
+                    .ok_or_else(|| format!("layer '{}' has no data", name))?;
+                let gids = match data.attribute("encoding") {
+                    Some("csv") => data
+                        .text()
+                        .unwrap_or("")
+                        .split(',')
+                        .map(|gid| gid.trim().parse::<u32>())
+                        .collect::<Result<Vec<u32>, _>>()
+                        .map_err(|_| format!("layer '{}' has a bad tile id", name))?,
+                    None => children(data, "tile")
+                        .map(|tile| match tile.attribute("gid") {
+                            Some(_) => attribute(tile, "gid"),
+                            None => Ok(0),
+                        })
+                        .collect::<Result<Vec<u32>, String>>()?,
+                    Some(encoding) => {
+                        return Err(format!(
+                            "layer '{}' is stored as {}, change its tile layer format to CSV",
+                            name, encoding
+                        ))
+                    }
+                };
+                map.tile_layers.push(TileLayer { name, gids });
+            }
+            "objectgroup" => {
+                for object in children(node, "object") {
+                    let class = object
+                        .attribute("class")
+                        .or_else(|| object.attribute("type"))
+                        .unwrap_or("");
+                    map.objects.push(TiledObject {
+                        name: object.attribute("name").unwrap_or("").to_string(),
+                        class: class.to_string(),
+                        x: attribute(object, "x")?,
+                        y: attribute(object, "y")?,
+                        width: optional_attribute(object, "width")?,
+                        height: optional_attribute(object, "height")?,
+                        rotation: optional_attribute(object, "rotation")?,
+                        properties: tmx_properties(object),
+                    });
+                }
+            }
+            "group" => tmx_layers(node, map)?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use map::document::from_rgba;
+
+    /// 3x3 room with a spawn, a lamp and tile 1 drawn as stone with id 5
+    const TMJ: &str = r##"{
+        "orientation": "orthogonal", "width": 3, "height": 3,
+        "tilewidth": 32, "tileheight": 32,
+        "layers": [
+            {"type": "tilelayer", "name": "walls", "data": [2,2,2, 2,0,2, 2,1,2]},
+            {"type": "tilelayer", "name": "ceiling", "data": [0,0,0, 0,2,0, 0,0,0]},
+            {"type": "objectgroup", "objects": [
+                {"class": "spawn", "x": 32, "y": 32, "width": 32, "height": 32,
+                 "properties": [{"name": "angle", "type": "float", "value": 90}]},
+                {"name": "lamp", "type": "light", "x": 48, "y": 40}
+            ]}
+        ],
+        "tilesets": [{"firstgid": 1, "tiles": [
+            {"id": 0, "properties": [
+                {"name": "tile_id", "type": "int", "value": 5},
+                {"name": "color", "type": "color", "value": "#ff102030"}
+            ]}
+        ]}]
+    }"##;
+
+    const TMX: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
+        <map orientation="orthogonal" width="3" height="3" tilewidth="32" tileheight="32">
+          <tileset firstgid="1">
+            <tile id="0">
+              <properties>
+                <property name="tile_id" type="int" value="5"/>
+                <property name="color" type="color" value="#ff102030"/>
+              </properties>
+            </tile>
+          </tileset>
+          <layer name="walls"><data encoding="csv">2,2,2,2,0,2,2,1,2</data></layer>
+          <layer name="ceiling"><data>
+            <tile/><tile/><tile/><tile/><tile gid="2"/><tile/><tile/><tile/><tile/>
+          </data></layer>
+          <objectgroup>
+            <object class="spawn" x="32" y="32" width="32" height="32">
+              <properties><property name="angle" type="float" value="90"/></properties>
+            </object>
+            <object name="lamp" type="light" x="48" y="40"/>
+          </objectgroup>
+        </map>"##;
+
+    fn json(text: &str) -> Result<Map, String> {
+        parse_json(text, 50.0, Path::new(""))
+    }
+
+    fn tmx(text: &str) -> Result<Map, String> {
+        parse_tmx(text, 50.0, Path::new(""))
+    }
+
+    #[test]
+    fn both_formats_read_the_same_map() {
+        for map in [json(TMJ).unwrap(), tmx(TMX).unwrap()].iter() {
+            let board = &map.board;
+            assert_eq!((board.tiles_x, board.tiles_y), (3, 3));
+            assert_eq!(board.tiles, vec![2, 2, 2, 2, 0, 2, 2, 5, 2]);
+            assert_eq!(board.ceiling_tiles[4], 2);
+            assert_eq!(board.info.tile_color(5), Some(from_rgba([16, 32, 48, 255])));
+
+            let spawn = map.spawns[0];
+            assert_eq!((spawn.position.x, spawn.position.y), (1.5, 1.5));
+            assert!((spawn.angle - 90f64.to_radians()).abs() < 1e-12);
+            let lamp = &map.entities[0];
+            assert_eq!((lamp.kind.as_str(), lamp.name.as_str()), ("light", "lamp"));
+            assert_eq!((lamp.position.x, lamp.position.y), (1.5, 1.25));
+        }
+    }
+
+    #[test]
+    fn only_orthogonal_maps_load() {
+        for orientation in ["isometric", "staggered", "hexagonal"].iter() {
+            let expected = format!(
+                "{} maps aren't supported, only orthogonal ones",
+                orientation
+            );
+            let text = TMJ.replace("\"orthogonal\"", &format!("\"{}\"", orientation));
+            assert_eq!(json(&text).err(), Some(expected.clone()));
+            let text = TMX.replace("\"orthogonal\"", &format!("\"{}\"", orientation));
+            assert_eq!(tmx(&text).err(), Some(expected));
+        }
+    }
+
+    #[test]
+    fn tiles_need_a_size() {
+        for size in ["0", "-32"].iter() {
+            let expected = format!("tiles are 32x{}, expected a width and height above 0", size);
+            let text = TMJ.replace("\"tileheight\": 32", &format!("\"tileheight\": {}", size));
+            assert_eq!(json(&text).err(), Some(expected.clone()));
+            let text = TMX.replace("tileheight=\"32\"", &format!("tileheight=\"{}\"", size));
+            assert_eq!(tmx(&text).err(), Some(expected));
+        }
+    }
+}
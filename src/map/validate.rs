@@ -0,0 +1,316 @@
+//! Checks a loaded map for mistakes that would crash the game or strand a player
+//!
+//! Floor is any tile players can walk on, empty or a passable kind from the tile
+//! registry. Players walk between floor tiles that share a side.
+
+use board::{Board, Tile};
+use map::Map;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// One thing wrong with a map, tile positions are `x, y` from the top left
+#[derive(Debug, Clone, PartialEq)]
+pub enum Problem {
+    /// the other checks are skipped, they can't index the board
+    TileCount {
+        expected: usize,
+        found: usize,
+    },
+    /// a tile id nothing knows how to draw, `count` tiles of it starting at `x, y`
+    UnknownTile {
+        tile: u32,
+        count: usize,
+        x: usize,
+        y: usize,
+    },
+    SpawnOutOfBounds {
+        spawn: usize,
+        x: f64,
+        y: f64,
+    },
+    SpawnInWall {
+        spawn: usize,
+        x: usize,
+        y: usize,
+    },
+    /// floor on the outer row or column, so rays and players can leave the board
+    OpenEdge {
+        count: usize,
+        x: usize,
+        y: usize,
+    },
+    /// a connected patch of floor no spawn can walk to
+    Unreachable {
+        count: usize,
+        x: usize,
+        y: usize,
+    },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Problem::TileCount { expected, found } => {
+                write!(f, "{} tiles, expected {}", found, expected)
+            }
+            Problem::UnknownTile { tile, count, x, y } => write!(
+                f,
+                "unknown tile id {} on {} tiles, first at {}, {}",
+                tile, count, x, y
+            ),
+            Problem::SpawnOutOfBounds { spawn, x, y } => {
+                write!(f, "spawn {} at {}, {} is off the board", spawn, x, y)
+            }
+            Problem::SpawnInWall { spawn, x, y } => {
+                write!(f, "spawn {} is inside the wall at {}, {}", spawn, x, y)
+            }
+            Problem::OpenEdge { count, x, y } => write!(
+                f,
+                "{} floor tiles on the map edge, first at {}, {}",
+                count, x, y
+            ),
+            Problem::Unreachable { count, x, y } => write!(
+                f,
+                "{} floor tiles around {}, {} can't be reached from a spawn",
+                count, x, y
+            ),
+        }
+    }
+}
+
+/// Every problem found in `map`, empty when it is fine to play
+pub fn validate(map: &Map) -> Vec<Problem> {
+    let board = &map.board;
+    let (width, height) = (board.tiles_x, board.tiles_y);
+    if board.tiles.len() != width * height {
+        return vec![Problem::TileCount {
+            expected: width * height,
+            found: board.tiles.len(),
+        }];
+    }
+    let mut problems = unknown_tiles(board);
+    let position = |index: usize| (index % width, index / width);
+
+    let mut starts = Vec::new();
+    for (i, spawn) in map.spawns.iter().enumerate() {
+        let (x, y) = (spawn.position.x, spawn.position.y);
+        if !(x >= 0.0 && y >= 0.0 && x < width as f64 && y < height as f64) {
+            problems.push(Problem::SpawnOutOfBounds { spawn: i, x, y });
+            continue;
+        }
+        let index = y as usize * width + x as usize;
+        if board.is_solid(Tile(board.tiles[index])) {
+            let (x, y) = position(index);
+            problems.push(Problem::SpawnInWall { spawn: i, x, y });
+            continue;
+        }
+        starts.push(index);
+    }
+
+    let edge = (0..board.tiles.len())
+        .filter(|&index| {
+            let (x, y) = position(index);
+            !board.is_solid(Tile(board.tiles[index]))
+                && (x == 0 || y == 0 || x == width - 1 || y == height - 1)
+        })
+        .collect::<Vec<usize>>();
+    if let Some(&first) = edge.first() {
+        let (x, y) = position(first);
+        problems.push(Problem::OpenEdge {
+            count: edge.len(),
+            x,
+            y,
+        });
+    }
+
+    // every spawn's region first, then whatever floor is left is stranded
+    let mut region = vec![false; board.tiles.len()];
+    fill(map, &starts, &mut region);
+    for index in 0..board.tiles.len() {
+        if !board.is_solid(Tile(board.tiles[index])) && !region[index] {
+            let (x, y) = position(index);
+            let count = fill(map, &[index], &mut region);
+            problems.push(Problem::Unreachable { count, x, y });
+        }
+    }
+    problems
+}
+
+/// An `UnknownTile` for every wall id on `board` that nothing knows how to draw
+pub fn unknown_tiles(board: &Board) -> Vec<Problem> {
+    // tile id -> count and first index
+    let mut unknown: BTreeMap<u32, (usize, usize)> = BTreeMap::new();
+    for (index, &tile) in board.tiles.iter().enumerate() {
+        if tile != 0 && board.wall_color(tile).is_none() {
+            unknown.entry(tile).or_insert((0, index)).0 += 1;
+        }
+    }
+    unknown
+        .into_iter()
+        .map(|(tile, (count, index))| Problem::UnknownTile {
+            tile,
+            count,
+            x: index % board.tiles_x,
+            y: index / board.tiles_x,
+        })
+        .collect()
+}
+
+/// Marks the floor reachable from `starts` in `seen`, returning how many tiles
+/// were newly marked
+fn fill(map: &Map, starts: &[usize], seen: &mut [bool]) -> usize {
+    let board = &map.board;
+    let (width, height) = (board.tiles_x, board.tiles_y);
+    let mut stack = Vec::new();
+    let mut count = 0;
+    for &start in starts.iter() {
+        if !seen[start] {
+            seen[start] = true;
+            stack.push(start);
+        }
+    }
+    while let Some(index) = stack.pop() {
+        count += 1;
+        let (x, y) = (index % width, index / width);
+        let mut neighbours = Vec::with_capacity(4);
+        if x > 0 {
+            neighbours.push(index - 1);
+        }
+        if x + 1 < width {
+            neighbours.push(index + 1);
+        }
+        if y > 0 {
+            neighbours.push(index - width);
+        }
+        if y + 1 < height {
+            neighbours.push(index + width);
+        }
+        for next in neighbours {
+            if !board.is_solid(Tile(board.tiles[next])) && !seen[next] {
+                seen[next] = true;
+                stack.push(next);
+            }
+        }
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use map;
+    use map::ascii;
+    use map::picture::Legend;
+    use map::Spawn;
+    use point::BoardPoint;
+    use tiles::TileRegistry;
+
+    fn with_registry(mut map: Map) -> Map {
+        map.board.registry = TileRegistry::load("assets/tiles.ron").unwrap();
+        map
+    }
+
+    fn problems(rows: &str) -> Vec<Problem> {
+        let map = ascii::parse(&format!("map\n{}", rows), 50.0).unwrap();
+        validate(&with_registry(map))
+    }
+
+    #[test]
+    fn shipped_maps_are_fine() {
+        for path in [
+            "assets/maps/default.txt",
+            "assets/maps/foggy.ron",
+            "assets/maps/hall.tmj",
+        ]
+        .iter()
+        {
+            let map = map::load(path, 50.0, &Legend::default()).unwrap();
+            assert_eq!(validate(&with_registry(map)), vec![], "{}", path);
+        }
+    }
+
+    #[test]
+    fn short_boards_skip_the_other_checks() {
+        let mut map = with_registry(ascii::parse("map\n111\n1>1\n111\n", 50.0).unwrap());
+        map.board.tiles.pop();
+        assert_eq!(
+            validate(&map),
+            vec![Problem::TileCount {
+                expected: 9,
+                found: 8
+            }]
+        );
+    }
+
+    #[test]
+    fn unknown_tiles_are_counted_from_their_first() {
+        let mut map = with_registry(ascii::parse("map\n1111\n1>.1\n1111\n", 50.0).unwrap());
+        map.board.tiles[3] = 99;
+        map.board.tiles[7] = 99;
+        assert_eq!(
+            validate(&map),
+            vec![Problem::UnknownTile {
+                tile: 99,
+                count: 2,
+                x: 3,
+                y: 0
+            }]
+        );
+    }
+
+    #[test]
+    fn spawns_must_stand_on_the_board_and_off_walls() {
+        let mut map = with_registry(ascii::parse("map\n1111\n1>.1\n1111\n", 50.0).unwrap());
+        let spawn = map.spawns[0];
+        map.spawns.push(Spawn {
+            position: BoardPoint { x: 4.5, y: 1.5 },
+            ..spawn
+        });
+        map.spawns.push(Spawn {
+            position: BoardPoint { x: 3.5, y: 2.5 },
+            ..spawn
+        });
+        assert_eq!(
+            validate(&map),
+            vec![
+                Problem::SpawnOutOfBounds {
+                    spawn: 1,
+                    x: 4.5,
+                    y: 1.5
+                },
+                Problem::SpawnInWall {
+                    spawn: 2,
+                    x: 3,
+                    y: 2
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn floor_on_the_edge_is_an_open_edge() {
+        assert_eq!(
+            problems("1111\n1>..\n1111\n"),
+            vec![Problem::OpenEdge {
+                count: 1,
+                x: 3,
+                y: 1
+            }]
+        );
+    }
+
+    #[test]
+    fn floor_walled_off_from_every_spawn_is_unreachable() {
+        // tall grass (11) can be walked through, so it joins the rooms it touches
+        assert_eq!(
+            problems("1111111\n1>.1..1\n1111111\n"),
+            vec![Problem::Unreachable {
+                count: 2,
+                x: 4,
+                y: 1
+            }]
+        );
+        let map = ascii::parse("legend g 11\nmap\n1111111\n1>.g..1\n1111111\n", 50.0).unwrap();
+        assert_eq!(validate(&with_registry(map)), vec![]);
+    }
+}
@@ -0,0 +1,104 @@
+use graphics::{math::Matrix2d, Transformed};
+use layout::Rect;
+use point::ScreenPoint;
+
+const MIN_ZOOM: f64 = 0.1;
+const MAX_ZOOM: f64 = 8.0;
+/// Zoom change per mouse wheel notch
+const ZOOM_STEP: f64 = 1.1;
+
+/// Pan and zoom of the top-down 2d map inside its panel
+///
+/// Map space is the board drawn at `block_size` pixels per tile, the space
+/// `Board::draw` and `Board::point_from` work in.
+#[derive(Debug, Clone)]
+pub struct MapCamera {
+    /// map space point shown at the top left corner of the panel
+    pub offset: ScreenPoint,
+    pub zoom: f64,
+    /// keep the followed player in the middle of the panel
+    pub follow: bool,
+    /// last cursor position while the map is being dragged
+    drag_from: Option<ScreenPoint>,
+}
+
+impl Default for MapCamera {
+    fn default() -> MapCamera {
+        MapCamera {
+            offset: ScreenPoint { x: 0.0, y: 0.0 },
+            zoom: 1.0,
+            follow: false,
+            drag_from: None,
+        }
+    }
+}
+
+impl MapCamera {
+    /// Transform from map space to the window for a map drawn in `panel`
+    pub fn transform(&self, transform: Matrix2d, panel: Rect) -> Matrix2d {
+        transform
+            .trans(panel.x, panel.y)
+            .zoom(self.zoom)
+            .trans(-self.offset.x, -self.offset.y)
+    }
+
+    /// Inverse of `transform`, turns a window point into map space
+    pub fn to_map(&self, panel: Rect, screen_point: ScreenPoint) -> ScreenPoint {
+        ScreenPoint {
+            x: (screen_point.x - panel.x) / self.zoom + self.offset.x,
+            y: (screen_point.y - panel.y) / self.zoom + self.offset.y,
+        }
+    }
+
+    /// Zooms in for positive `notches`, keeping the map point under the cursor in place
+    pub fn zoom_at(&mut self, panel: Rect, cursor: ScreenPoint, notches: f64) {
+        let anchor = self.to_map(panel, cursor);
+        self.zoom = (self.zoom * ZOOM_STEP.powf(notches)).clamp(MIN_ZOOM, MAX_ZOOM);
+        self.offset = ScreenPoint {
+            x: anchor.x - (cursor.x - panel.x) / self.zoom,
+            y: anchor.y - (cursor.y - panel.y) / self.zoom,
+        };
+    }
+
+    pub fn start_drag(&mut self, cursor: ScreenPoint) {
+        self.drag_from = Some(cursor);
+    }
+
+    /// Pans with the cursor while dragging, which also stops following
+    pub fn drag_to(&mut self, cursor: ScreenPoint) {
+        if let Some(from) = self.drag_from {
+            self.offset.x -= (cursor.x - from.x) / self.zoom;
+            self.offset.y -= (cursor.y - from.y) / self.zoom;
+            self.drag_from = Some(cursor);
+            self.follow = false;
+        }
+    }
+
+    pub fn end_drag(&mut self) {
+        self.drag_from = None;
+    }
+
+    pub fn toggle_follow(&mut self) {
+        self.follow = !self.follow;
+    }
+
+    /// Centres the panel on `target`, a map space point, when following
+    pub fn follow(&mut self, panel: Rect, target: ScreenPoint) {
+        if self.follow {
+            self.offset = ScreenPoint {
+                x: target.x - panel.width / 2.0 / self.zoom,
+                y: target.y - panel.height / 2.0 / self.zoom,
+            };
+        }
+    }
+}
+
+impl std::fmt::Display for MapCamera {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "Map {{ x: {:.0}, y: {:.0}, zoom: {:.2}, follow: {} }}",
+            self.offset.x, self.offset.y, self.zoom, self.follow
+        )
+    }
+}
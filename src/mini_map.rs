@@ -1,6 +1,7 @@
 use board::Board;
 use colors;
 use graphics::{math::Matrix2d, Transformed};
+use layout::Rect;
 use player::Player;
 use point::BoardPoint;
 use sharp_graphics::SharpGraphics;
@@ -14,15 +15,15 @@ impl MiniMap {
         &self,
         transform: Matrix2d,
         graphics: &mut SharpGraphics,
+        rect: Rect,
         board: &Board,
         player: &Player,
     ) {
-        const X_OFFSET: f64 = 800.0;
-        const Y_OFFSET: f64 = 600.0;
         const TWO_PI: f64 = std::f64::consts::PI * 2.0;
         const BUMP: f64 = TWO_PI / 3600.0;
 
-        let origin = transform.trans(X_OFFSET, Y_OFFSET);
+        let center = rect.center();
+        let origin = transform.trans(center.x, center.y);
         graphics.draw_rectangle(
             colors::BLACK,
             [0.0, 0.0, 10.0, 10.0],
@@ -153,14 +154,7 @@ impl MiniMap {
             x: player.position.x,
             y: player.position.y - 1.0,
         };
-        let index = board.get_index(point);
-        let color_option = match board.tiles[index] {
-            1 => Some(colors::RED_ALPHA),
-            2 => Some(colors::BLUE_ALPHA),
-            3 => Some(colors::GREEN_ALPHA),
-            4 => Some(colors::ORANGE_ALPHA),
-            _ => None,
-        };
+        let color_option = board.map_color_at(point);
 
         if let Some(color) = color_option {
             graphics.draw_rectangle(
@@ -175,14 +169,7 @@ impl MiniMap {
             y: player.position.y - 1.0,
         };
 
-        let index = board.get_index(point);
-        let color_option = match board.tiles[index] {
-            1 => Some(colors::RED_ALPHA),
-            2 => Some(colors::BLUE_ALPHA),
-            3 => Some(colors::GREEN_ALPHA),
-            4 => Some(colors::ORANGE_ALPHA),
-            _ => None,
-        };
+        let color_option = board.map_color_at(point);
 
         if let Some(color) = color_option {
             graphics.draw_rectangle(
@@ -197,14 +184,7 @@ impl MiniMap {
             x: player.position.x,
             y: player.position.y + 1.0,
         };
-        let index = board.get_index(point);
-        let color_option = match board.tiles[index] {
-            1 => Some(colors::RED_ALPHA),
-            2 => Some(colors::BLUE_ALPHA),
-            3 => Some(colors::GREEN_ALPHA),
-            4 => Some(colors::ORANGE_ALPHA),
-            _ => None,
-        };
+        let color_option = board.map_color_at(point);
 
         if let Some(color) = color_option {
             graphics.draw_rectangle(
@@ -219,14 +199,7 @@ impl MiniMap {
             y: player.position.y + 1.0,
         };
 
-        let index = board.get_index(point);
-        let color_option = match board.tiles[index] {
-            1 => Some(colors::RED_ALPHA),
-            2 => Some(colors::BLUE_ALPHA),
-            3 => Some(colors::GREEN_ALPHA),
-            4 => Some(colors::ORANGE_ALPHA),
-            _ => None,
-        };
+        let color_option = board.map_color_at(point);
 
         if let Some(color) = color_option {
             graphics.draw_rectangle(
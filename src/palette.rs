@@ -0,0 +1,257 @@
+use frame::Frame;
+use map::document::{from_rgba, Fog};
+
+pub const PALETTE_SIZE: usize = 256;
+/// Rows in the colour map, row 0 is full bright and the last row is near black
+pub const LIGHT_LEVELS: usize = 32;
+/// Light levels lost per tile of distance from the camera
+const LEVELS_PER_TILE: f64 = 1.25;
+/// Extra darkening for walls facing east or west, so corners stay readable
+const SIDE_LEVELS: usize = 3;
+
+/// 256 RGB entries
+#[derive(Clone)]
+pub struct Palette {
+    pub colors: [[u8; 3]; PALETTE_SIZE],
+}
+
+impl Palette {
+    /// Reads a JASC-PAL text palette, or a raw palette of 768 RGB bytes such as a
+    /// PLAYPAL lump, where only the first palette is used
+    pub fn load(path: &str) -> Result<Palette, String> {
+        let bytes = std::fs::read(path).map_err(|e| format!("could not read '{}': {}", path, e))?;
+        if bytes.starts_with(b"JASC-PAL") {
+            Palette::parse_jasc(&String::from_utf8_lossy(&bytes))
+                .map_err(|e| format!("{}: {}", path, e))
+        } else {
+            Palette::from_raw(&bytes).map_err(|e| format!("{}: {}", path, e))
+        }
+    }
+
+    pub fn from_raw(bytes: &[u8]) -> Result<Palette, String> {
+        if bytes.len() < PALETTE_SIZE * 3 {
+            return Err(format!(
+                "raw palette needs {} bytes, found {}",
+                PALETTE_SIZE * 3,
+                bytes.len()
+            ));
+        }
+        let mut colors = [[0; 3]; PALETTE_SIZE];
+        for (color, rgb) in colors.iter_mut().zip(bytes.chunks(3)) {
+            color.copy_from_slice(rgb);
+        }
+        Ok(Palette { colors })
+    }
+
+    pub fn parse_jasc(text: &str) -> Result<Palette, String> {
+        let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+        if lines.next() != Some("JASC-PAL") || lines.next() != Some("0100") {
+            return Err("missing JASC-PAL header".to_string());
+        }
+        let count = lines
+            .next()
+            .and_then(|line| line.parse::<usize>().ok())
+            .ok_or_else(|| "missing colour count".to_string())?;
+        if count > PALETTE_SIZE {
+            return Err(format!(
+                "{} colours, at most {} allowed",
+                count, PALETTE_SIZE
+            ));
+        }
+        let mut colors = [[0; 3]; PALETTE_SIZE];
+        for (i, color) in colors.iter_mut().enumerate().take(count) {
+            let line = lines
+                .next()
+                .ok_or_else(|| format!("expected {} colours, found {}", count, i))?;
+            let rgb = line
+                .split_whitespace()
+                .map(|value| value.parse::<u8>())
+                .collect::<Result<Vec<u8>, _>>()
+                .map_err(|_| format!("bad colour '{}'", line))?;
+            if rgb.len() != 3 {
+                return Err(format!("bad colour '{}'", line));
+            }
+            color.copy_from_slice(&rgb);
+        }
+        Ok(Palette { colors })
+    }
+
+    /// Every entry as an opaque RGBA8 pixel, the table indexed frames resolve through
+    pub fn rgba(&self) -> [[u8; 4]; PALETTE_SIZE] {
+        let mut pixels = [[0, 0, 0, 255]; PALETTE_SIZE];
+        for (pixel, color) in pixels.iter_mut().zip(self.colors.iter()) {
+            pixel[..3].copy_from_slice(color);
+        }
+        pixels
+    }
+
+    /// Index of the palette entry closest to `rgb`
+    pub fn nearest(&self, rgb: [u8; 3]) -> u8 {
+        let distance = |color: &[u8; 3]| -> i32 {
+            color
+                .iter()
+                .zip(rgb.iter())
+                .map(|(&a, &b)| (i32::from(a) - i32::from(b)).pow(2))
+                .sum()
+        };
+        (0..PALETTE_SIZE)
+            .min_by_key(|&i| distance(&self.colors[i]))
+            .unwrap_or(0) as u8
+    }
+}
+
+/// Light level tables: `levels[level][index]` is the palette index showing
+/// colour `index` at that light level
+pub struct ColorMap {
+    pub levels: Vec<[u8; PALETTE_SIZE]>,
+}
+
+impl ColorMap {
+    /// Fades every entry linearly towards black, snapping to the nearest entry
+    pub fn build(palette: &Palette) -> ColorMap {
+        ColorMap::fade_to(palette, [0, 0, 0])
+    }
+
+    /// Fades every entry linearly towards `target`, reaching it past the last level
+    pub fn fade_to(palette: &Palette, target: [u8; 3]) -> ColorMap {
+        let levels = (0..LIGHT_LEVELS)
+            .map(|level| {
+                let amount = level as f64 / LIGHT_LEVELS as f64;
+                let mix = |from: u8, to: u8| {
+                    (f64::from(from) + (f64::from(to) - f64::from(from)) * amount).round() as u8
+                };
+                let mut row = [0u8; PALETTE_SIZE];
+                for (entry, color) in row.iter_mut().zip(palette.colors.iter()) {
+                    let faded = [
+                        mix(color[0], target[0]),
+                        mix(color[1], target[1]),
+                        mix(color[2], target[2]),
+                    ];
+                    *entry = palette.nearest(faded);
+                }
+                row
+            })
+            .collect();
+        ColorMap { levels }
+    }
+
+    pub fn shade(&self, index: u8, level: usize) -> u8 {
+        self.levels[level.min(LIGHT_LEVELS - 1)][index as usize]
+    }
+}
+
+/// Everything the indexed renderer needs to pick and light a palette entry
+pub struct IndexedColors {
+    pub palette: Palette,
+    pub color_map: ColorMap,
+    /// fades towards the map's fog colour, `None` when the map has no fog
+    pub fog_map: Option<ColorMap>,
+}
+
+impl IndexedColors {
+    pub fn new(palette: Palette) -> IndexedColors {
+        IndexedColors {
+            color_map: ColorMap::build(&palette),
+            fog_map: None,
+            palette,
+        }
+    }
+
+    /// Builds `fog_map` for `fog`, indexed pixels can't blend so fog is
+    /// shaded through its own colour map instead
+    pub fn with_fog(mut self, fog: Option<Fog>) -> IndexedColors {
+        self.fog_map =
+            fog.map(|fog| ColorMap::fade_to(&self.palette, to_rgb(from_rgba(fog.color))));
+        self
+    }
+
+    pub fn load(path: &str) -> Result<IndexedColors, String> {
+        Palette::load(path).map(IndexedColors::new)
+    }
+
+    /// Palette entry closest to an RGBA colour
+    pub fn color_index(&self, color: [f32; 4]) -> u8 {
+        self.palette.nearest(to_rgb(color))
+    }
+
+    /// Row of `fog_map` for fog covering `amount` of a colour, `0.0..=1.0`
+    pub fn fog_level(&self, amount: f32) -> usize {
+        (amount * LIGHT_LEVELS as f32).round() as usize
+    }
+
+    /// Light level for a wall `distance` tiles away
+    pub fn wall_level(&self, distance: f64, east_west: bool) -> usize {
+        let level = (distance * LEVELS_PER_TILE) as usize;
+        if east_west {
+            level + SIDE_LEVELS
+        } else {
+            level
+        }
+    }
+}
+
+/// One palette index per pixel
+pub struct IndexedFrame {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl IndexedFrame {
+    pub fn new(width: u32, height: u32) -> IndexedFrame {
+        let (width, height) = (width.max(1), height.max(1));
+        IndexedFrame {
+            width,
+            height,
+            pixels: vec![0; (width * height) as usize],
+        }
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if self.width != width.max(1) || self.height != height.max(1) {
+            *self = IndexedFrame::new(width, height);
+        }
+    }
+
+    /// Fills rows `top..bottom` of column `x`, clipped to the frame
+    pub fn fill_column(&mut self, x: u32, top: u32, bottom: u32, index: u8) {
+        if x >= self.width {
+            return;
+        }
+        for y in top..bottom.min(self.height) {
+            self.pixels[(y * self.width + x) as usize] = index;
+        }
+    }
+
+    /// Writes columns `left..left + width` to `frame` through `pixels`, one
+    /// RGBA8 pixel per palette entry
+    pub fn resolve(
+        &self,
+        pixels: &[[u8; 4]; PALETTE_SIZE],
+        frame: &mut Frame,
+        left: u32,
+        width: u32,
+    ) {
+        let frame_width = frame.width() as usize;
+        let right = (left + width).min(self.width).min(frame.width()) as usize;
+        let left = (left as usize).min(right);
+        let rows = self.pixels.chunks(self.width as usize);
+        for (row, out) in rows.zip(frame.pixels_mut().chunks_mut(frame_width * 4)) {
+            for (&index, out) in row[left..right]
+                .iter()
+                .zip(out[left * 4..right * 4].chunks_mut(4))
+            {
+                out.copy_from_slice(&pixels[index as usize]);
+            }
+        }
+    }
+}
+
+fn to_rgb(color: [f32; 4]) -> [u8; 3] {
+    let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    [channel(color[0]), channel(color[1]), channel(color[2])]
+}
@@ -0,0 +1,398 @@
+//! Routes across the board for anything that walks it
+//!
+//! A route goes from tile to tile through tiles players can walk into, paying
+//! the movement cost of every tile it enters. Diagonal steps never cut past the
+//! corner of a wall, so both tiles beside the step must be walkable too. Routes
+//! stay on the board whatever its `out_of_bounds` policy says.
+
+use board::Board;
+use point::BoardPoint;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::f64::consts::SQRT_2;
+
+/// How far apart tile boundary crossings can be and still count as one corner
+const CORNER_EPSILON: f64 = 1e-9;
+
+/// Which neighbours a route can step to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Movement {
+    /// the tiles sharing a side
+    Four,
+    /// the tiles sharing a side or a corner
+    Eight,
+}
+
+impl Movement {
+    pub fn parse(value: &str) -> Option<Movement> {
+        match value {
+            "4" => Some(Movement::Four),
+            "8" => Some(Movement::Eight),
+            _ => None,
+        }
+    }
+
+    /// Cheapest possible cost between two tiles, every tile costs at least 1
+    fn estimate(self, dx: usize, dy: usize) -> f64 {
+        let (long, short) = (dx.max(dy) as f64, dx.min(dy) as f64);
+        match self {
+            Movement::Four => long + short,
+            Movement::Eight => long + (SQRT_2 - 1.0) * short,
+        }
+    }
+}
+
+/// Tile on the open list, ordered so the heap pops the lowest estimate first
+struct Open {
+    estimate: f64,
+    index: usize,
+}
+
+impl PartialEq for Open {
+    fn eq(&self, other: &Open) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Open {}
+
+impl PartialOrd for Open {
+    fn partial_cmp(&self, other: &Open) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Open {
+    fn cmp(&self, other: &Open) -> Ordering {
+        other
+            .estimate
+            .total_cmp(&self.estimate)
+            .then_with(|| other.index.cmp(&self.index))
+    }
+}
+
+/// Board indices of the cheapest route from the tile holding `from` to the tile
+/// holding `to`, both ends included
+///
+/// `None` when either end is off the board or inside a solid tile, or nothing
+/// walkable joins them.
+pub fn find_cells(
+    board: &Board,
+    from: BoardPoint,
+    to: BoardPoint,
+    movement: Movement,
+) -> Option<Vec<usize>> {
+    let start = board.get_index(from)?;
+    let goal = board.get_index(to)?;
+    cost_at(board, start)?;
+    cost_at(board, goal)?;
+
+    let width = board.tiles_x;
+    let (goal_x, goal_y) = (goal % width, goal / width);
+    let estimate = |index: usize| {
+        let (x, y) = (index % width, index / width);
+        movement.estimate(x.abs_diff(goal_x), y.abs_diff(goal_y))
+    };
+
+    let mut costs = vec![f64::INFINITY; board.tiles.len()];
+    let mut came_from = vec![None; board.tiles.len()];
+    let mut open = BinaryHeap::new();
+    costs[start] = 0.0;
+    open.push(Open {
+        estimate: estimate(start),
+        index: start,
+    });
+    while let Some(Open {
+        estimate: best,
+        index,
+    }) = open.pop()
+    {
+        if index == goal {
+            let mut cells = vec![goal];
+            let mut current = goal;
+            while let Some(previous) = came_from[current] {
+                cells.push(previous);
+                current = previous;
+            }
+            cells.reverse();
+            return Some(cells);
+        }
+        // a cheaper way here was found after this entry was pushed
+        if best > costs[index] + estimate(index) {
+            continue;
+        }
+        for (next, distance) in neighbours(board, index, movement) {
+            let step = match cost_at(board, next) {
+                Some(step) => step,
+                None => continue,
+            };
+            let cost = costs[index] + distance * step;
+            if cost < costs[next] {
+                costs[next] = cost;
+                came_from[next] = Some(index);
+                open.push(Open {
+                    estimate: cost + estimate(next),
+                    index: next,
+                });
+            }
+        }
+    }
+    None
+}
+
+/// Waypoints from `from` to `to` along the cheapest route, `None` when there
+/// is none
+///
+/// The points between the ends are tile centres. Four way routes keep only
+/// the tiles they turn on. Eight way routes are also pulled straight, wherever
+/// a line crosses no wall, cuts no corner and enters no tile dearer than the
+/// ones it replaces.
+pub fn find_path(
+    board: &Board,
+    from: BoardPoint,
+    to: BoardPoint,
+    movement: Movement,
+) -> Option<Vec<BoardPoint>> {
+    let cells = find_cells(board, from, to, movement)?;
+    let mut points = cells
+        .iter()
+        .map(|&index| {
+            let corner = board.get_tile(index);
+            BoardPoint {
+                x: corner.x + 0.5,
+                y: corner.y + 0.5,
+            }
+        })
+        .collect::<Vec<BoardPoint>>();
+    let last = points.len() - 1;
+    points[0] = from;
+    points[last] = to;
+
+    let mut waypoints = vec![from];
+    let mut anchor = 0;
+    while anchor < last {
+        let mut next = anchor + 1;
+        for candidate in anchor + 2..=last {
+            let skips = match movement {
+                Movement::Four => in_line(&cells, anchor, candidate, board.tiles_x),
+                Movement::Eight => {
+                    let dearest = cells[anchor..=candidate]
+                        .iter()
+                        .filter_map(|&index| cost_at(board, index))
+                        .fold(1.0, f64::max);
+                    is_clear(board, points[anchor], points[candidate], dearest)
+                }
+            };
+            if skips {
+                next = candidate;
+            }
+        }
+        waypoints.push(points[next]);
+        anchor = next;
+    }
+    Some(waypoints)
+}
+
+/// Movement cost of the tile at `index`, `None` off the board or when it is solid
+fn cost_at(board: &Board, index: usize) -> Option<f64> {
+    board.tile(index).and_then(|tile| board.move_cost(tile))
+}
+
+/// Tiles a route can step to from `index`, with the length of the step
+fn neighbours(board: &Board, index: usize, movement: Movement) -> Vec<(usize, f64)> {
+    let (x, y) = (
+        (index % board.tiles_x) as i64,
+        (index / board.tiles_x) as i64,
+    );
+    let walkable = |dx: i64, dy: i64| {
+        let (nx, ny) = (x + dx, y + dy);
+        if nx < 0 || ny < 0 {
+            return None;
+        }
+        board
+            .get_index_from_tile(nx as usize, ny as usize)
+            .filter(|&next| cost_at(board, next).is_some())
+    };
+    let mut neighbours = Vec::with_capacity(8);
+    for &(dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)].iter() {
+        neighbours.extend(walkable(dx, dy).map(|next| (next, 1.0)));
+    }
+    if movement == Movement::Eight {
+        for &(dx, dy) in [(1, 1), (1, -1), (-1, 1), (-1, -1)].iter() {
+            if walkable(dx, 0).is_none() || walkable(0, dy).is_none() {
+                continue;
+            }
+            neighbours.extend(walkable(dx, dy).map(|next| (next, SQRT_2)));
+        }
+    }
+    neighbours
+}
+
+/// Whether `cells[from..=to]` all lie along one row or one column
+fn in_line(cells: &[usize], from: usize, to: usize, width: usize) -> bool {
+    let (x, y) = (cells[from] % width, cells[from] / width);
+    cells[from..=to].iter().all(|&index| index % width == x)
+        || cells[from..=to].iter().all(|&index| index / width == y)
+}
+
+/// Whether a straight walk from `from` to `to` only enters walkable tiles
+/// costing at most `dearest`, passing corners only between two such tiles
+fn is_clear(board: &Board, from: BoardPoint, to: BoardPoint, dearest: f64) -> bool {
+    let walkable = |x: i64, y: i64| {
+        x >= 0
+            && y >= 0
+            && board
+                .get_index_from_tile(x as usize, y as usize)
+                .and_then(|index| cost_at(board, index))
+                .is_some_and(|cost| cost <= dearest)
+    };
+    let (dx, dy) = (to.x - from.x, to.y - from.y);
+    let (mut x, mut y) = (from.x.floor() as i64, from.y.floor() as i64);
+    let (end_x, end_y) = (to.x.floor() as i64, to.y.floor() as i64);
+    let (step_x, step_y) = (if dx > 0.0 { 1 } else { -1 }, if dy > 0.0 { 1 } else { -1 });
+    // how far along the line, 0 to 1, the next column and row boundaries are
+    let boundary = |start: f64, tile: i64, delta: f64| {
+        if delta > 0.0 {
+            (tile as f64 + 1.0 - start) / delta
+        } else if delta < 0.0 {
+            (tile as f64 - start) / delta
+        } else {
+            f64::INFINITY
+        }
+    };
+    let mut next_x = boundary(from.x, x, dx);
+    let mut next_y = boundary(from.y, y, dy);
+    let (step_along_x, step_along_y) = (1.0 / dx.abs(), 1.0 / dy.abs());
+
+    if !walkable(x, y) {
+        return false;
+    }
+    while (x, y) != (end_x, end_y) {
+        if (next_x - next_y).abs() < CORNER_EPSILON {
+            if !walkable(x + step_x, y) || !walkable(x, y + step_y) {
+                return false;
+            }
+            x += step_x;
+            y += step_y;
+            next_x += step_along_x;
+            next_y += step_along_y;
+        } else if next_x < next_y {
+            x += step_x;
+            next_x += step_along_x;
+        } else {
+            y += step_y;
+            next_y += step_along_y;
+        }
+        if next_x.min(next_y) > 1.0 + CORNER_EPSILON && (x, y) != (end_x, end_y) {
+            // float error carried the walk past the end tile
+            return walkable(end_x, end_y);
+        }
+        if !walkable(x, y) {
+            return false;
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_board::{board_from, GRASS};
+
+    fn centre(x: usize, y: usize) -> BoardPoint {
+        BoardPoint {
+            x: x as f64 + 0.5,
+            y: y as f64 + 0.5,
+        }
+    }
+
+    fn steps(board: &Board, cells: &[usize]) -> Vec<(i64, i64)> {
+        let width = board.tiles_x as i64;
+        cells
+            .windows(2)
+            .map(|pair| {
+                let (from, to) = (pair[0] as i64, pair[1] as i64);
+                (to % width - from % width, to / width - from / width)
+            })
+            .collect()
+    }
+
+    const ROOM: [&str; 5] = ["#####", "#...#", "#...#", "#...#", "#####"];
+
+    #[test]
+    fn four_way_routes_only_step_across_sides() {
+        let board = board_from(&ROOM);
+        let cells = find_cells(&board, centre(1, 1), centre(3, 3), Movement::Four).unwrap();
+        assert_eq!(cells.len(), 5);
+        for (dx, dy) in steps(&board, &cells) {
+            assert_eq!(dx.abs() + dy.abs(), 1);
+        }
+    }
+
+    #[test]
+    fn eight_way_routes_step_diagonally() {
+        let board = board_from(&ROOM);
+        let cells = find_cells(&board, centre(1, 1), centre(3, 3), Movement::Eight).unwrap();
+        assert_eq!(cells, vec![6, 12, 18]);
+    }
+
+    #[test]
+    fn diagonal_steps_never_cut_corners() {
+        let board = board_from(&["#####", "#...#", "#.#.#", "#...#", "#####"]);
+        let cells = find_cells(&board, centre(1, 1), centre(3, 3), Movement::Eight).unwrap();
+        // every diagonal step around the pillar would clip it
+        assert_eq!(cells.len(), 5);
+        let width = board.tiles_x as i64;
+        for (&from, (dx, dy)) in cells.iter().zip(steps(&board, &cells)) {
+            let (x, y) = (from as i64 % width, from as i64 / width);
+            if dx != 0 && dy != 0 {
+                assert!(!board.is_wall_at(centre((x + dx) as usize, y as usize)));
+                assert!(!board.is_wall_at(centre(x as usize, (y + dy) as usize)));
+            }
+        }
+    }
+
+    #[test]
+    fn routes_go_around_costly_tiles() {
+        // crossing the grass costs 10, the detour below it 8
+        let board = board_from(&["#######", "#.ggg.#", "#.###.#", "#.....#", "#######"]);
+        let cells = find_cells(&board, centre(1, 1), centre(5, 1), Movement::Four).unwrap();
+        assert!(cells.iter().all(|&index| board.tiles[index] != GRASS));
+        assert_eq!(cells.len(), 9);
+
+        // one grass tile makes crossing cost 6, cheaper than the detour
+        let board = board_from(&["#######", "#.g...#", "#.###.#", "#.....#", "#######"]);
+        let cells = find_cells(&board, centre(1, 1), centre(5, 1), Movement::Four).unwrap();
+        assert_eq!(cells.len(), 5);
+    }
+
+    #[test]
+    fn no_route_gives_none() {
+        let board = board_from(&["#####", "#.#.#", "#####"]);
+        assert_eq!(
+            find_cells(&board, centre(1, 1), centre(3, 1), Movement::Eight),
+            None
+        );
+        assert!(find_path(&board, centre(1, 1), centre(3, 1), Movement::Four).is_none());
+        // ends inside a wall or off the board
+        assert_eq!(
+            find_cells(&board, centre(1, 1), centre(2, 1), Movement::Four),
+            None
+        );
+        let off = BoardPoint { x: -1.0, y: 1.5 };
+        assert_eq!(find_cells(&board, off, centre(1, 1), Movement::Four), None);
+    }
+
+    #[test]
+    fn paths_are_pulled_straight_across_open_floor() {
+        let board = board_from(&ROOM);
+        let (from, to) = (centre(1, 1), BoardPoint { x: 3.2, y: 3.7 });
+        let path = find_path(&board, from, to, Movement::Eight).unwrap();
+        assert_eq!(path.len(), 2);
+        assert_eq!((path[1].x, path[1].y), (to.x, to.y));
+
+        // four way paths keep the tile they turn on
+        let path = find_path(&board, from, centre(3, 3), Movement::Four).unwrap();
+        assert_eq!(path.len(), 3);
+    }
+}
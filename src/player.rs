@@ -1,10 +1,19 @@
 use board::Board;
 use colors;
+use debug_overlay::{DebugLayers, Layer};
 use graphics::{math::Matrix2d, Transformed};
+use input::Action;
 use point::BoardPoint;
 use point::InterceptType;
 use ray::Ray;
+use rayon::prelude::*;
 use sharp_graphics::SharpGraphics;
+use sight;
+
+/// Field of view the rays are spread across, in radians
+pub const FOV: f64 = std::f64::consts::FRAC_PI_2;
+/// Rays cast for the sight debug layer
+const SIGHT_RAYS: usize = 120;
 
 #[derive(Debug)]
 pub struct Player {
@@ -13,11 +22,14 @@ pub struct Player {
     pub angle_tick: f64,
     pub rays: Vec<Ray>,
     pub move_step: f64,
+    /// cast the ray batch on the rayon thread pool
+    pub parallel: bool,
+    /// waypoints still to walk to, in order, from click to move
+    pub route: Vec<BoardPoint>,
 }
 
 impl std::fmt::Display for Player {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        let _temp = vec![1].iter().filter(|_| true).collect::<Vec<&i32>>().len();
         write!(
             f,
             "Player {{ pos: {}, xes: {:?}, yes: {:?}, rot: {}}}",
@@ -49,24 +61,48 @@ impl std::fmt::Display for Player {
 
 impl Player {
     pub fn update(&mut self, board: &Board) {
-        let fov = std::f64::consts::PI / 2.0;
-        let ray_angle_tick = if self.rays.len() > 1 {
-            fov / (self.rays.len() - 1) as f64
+        let mut rays = std::mem::take(&mut self.rays);
+        self.cast_rays(self.position, &mut rays, FOV, board);
+        self.rays = rays;
+    }
+
+    /// Spreads `rays` across `fov` radians centred on the player's angle, as seen
+    /// from `origin`
+    pub fn cast_rays(&self, origin: BoardPoint, rays: &mut [Ray], fov: f64, board: &Board) {
+        let ray_angle_tick = if rays.len() > 1 {
+            fov / (rays.len() - 1) as f64
         } else {
             0.0
         };
-        let start_rotation = if self.rays.len() > 1 {
+        let start_rotation = if rays.len() > 1 {
             self.angle - fov / 2.0
         } else {
             self.angle
         };
-        for (i, ray) in self.rays.iter_mut().enumerate() {
+        let update_ray = |(i, ray): (usize, &mut Ray)| {
             let ray_angle = start_rotation + ray_angle_tick * i as f64;
-            ray.update(self.position, ray_angle, board);
+            ray.update(origin, ray_angle, board);
+        };
+        if self.parallel {
+            rays.par_iter_mut().enumerate().for_each(update_ray);
+        } else {
+            rays.iter_mut().enumerate().for_each(update_ray);
+        }
+    }
+
+    pub fn set_ray_count(&mut self, ray_count: usize) {
+        if self.rays.len() != ray_count {
+            self.rays.resize(ray_count, Ray::new());
         }
     }
 
-    pub fn draw(&self, transform: Matrix2d, graphics: &mut SharpGraphics, board: &Board) {
+    pub fn draw(
+        &self,
+        transform: Matrix2d,
+        graphics: &mut SharpGraphics,
+        board: &Board,
+        layers: &DebugLayers,
+    ) {
         const PLAYER_RECT_WIDTH: f64 = 10.0;
         const PLAYER_RECT_WIDTH_HALF: f64 = PLAYER_RECT_WIDTH / -2.0;
 
@@ -88,7 +124,140 @@ impl Player {
         );
 
         for ray in self.rays.iter() {
-            ray.draw(transform, graphics, board.block_size);
+            if layers.is_on(Layer::Intercepts) {
+                ray.draw_intercepts(transform, graphics);
+            }
+            if layers.is_on(Layer::RayFan) {
+                ray.draw_fan(transform, graphics, board.block_size);
+            }
+            if layers.is_on(Layer::HitPoints) {
+                ray.draw_hit(transform, graphics);
+            }
+        }
+
+        if layers.is_on(Layer::CollisionBox) {
+            self.draw_collision_box(transform, graphics, board);
+        }
+        if layers.is_on(Layer::Sight) {
+            self.draw_sight(transform, graphics, board);
+        }
+
+        let mut from = self.position;
+        for &to in self.route.iter() {
+            graphics.draw_line(
+                colors::ORANGE_ALPHA,
+                [
+                    from.x * board.block_size,
+                    from.y * board.block_size,
+                    to.x * board.block_size,
+                    to.y * board.block_size,
+                ],
+                transform,
+            );
+            from = to;
+        }
+    }
+
+    /// Outlines the tile the player is in and the square reachable in one move step
+    fn draw_collision_box(&self, transform: Matrix2d, graphics: &mut SharpGraphics, board: &Board) {
+        let tile_x = self.position.x.floor() * board.block_size;
+        let tile_y = self.position.y.floor() * board.block_size;
+        let reach = self.move_step * board.block_size;
+        let x = self.position.x * board.block_size;
+        let y = self.position.y * board.block_size;
+        let outline = |graphics: &mut SharpGraphics, color, [left, top, size]: [f64; 3]| {
+            let (right, bottom) = (left + size, top + size);
+            graphics.draw_line(color, [left, top, right, top], transform);
+            graphics.draw_line(color, [right, top, right, bottom], transform);
+            graphics.draw_line(color, [right, bottom, left, bottom], transform);
+            graphics.draw_line(color, [left, bottom, left, top], transform);
+        };
+        outline(
+            graphics,
+            colors::GREEN_ALPHA,
+            [tile_x, tile_y, board.block_size],
+        );
+        outline(
+            graphics,
+            colors::RED_ALPHA,
+            [x - reach, y - reach, reach * 2.0],
+        );
+    }
+
+    /// Shades the tiles in view and outlines the area seen
+    fn draw_sight(&self, transform: Matrix2d, graphics: &mut SharpGraphics, board: &Board) {
+        let position = self.position;
+        for index in sight::visible_tiles(board, position, self.angle, FOV, SIGHT_RAYS) {
+            let tile = board.get_tile(index);
+            graphics.draw_rectangle(
+                colors::SIGHT,
+                [
+                    tile.x * board.block_size,
+                    tile.y * board.block_size,
+                    board.block_size,
+                    board.block_size,
+                ],
+                transform,
+            );
+        }
+        let polygon = sight::visibility_polygon(board, position, self.angle, FOV, SIGHT_RAYS);
+        for (i, from) in polygon.iter().enumerate() {
+            let to = polygon[(i + 1) % polygon.len()];
+            graphics.draw_line(
+                colors::GREEN_ALPHA,
+                [
+                    from.x * board.block_size,
+                    from.y * board.block_size,
+                    to.x * board.block_size,
+                    to.y * board.block_size,
+                ],
+                transform,
+            );
+        }
+    }
+
+    /// Walks `distance` tiles along the route, facing the way it goes, and
+    /// gives the route up if a wall has been put in the way
+    pub fn follow_route(&mut self, mut distance: f64, board: &Board) {
+        while let Some(&target) = self.route.first() {
+            let (dx, dy) = (target.x - self.position.x, target.y - self.position.y);
+            let remaining = dx.hypot(dy);
+            if remaining > 0.0 {
+                self.angle = dy.atan2(dx);
+            }
+            let new_point = if remaining <= distance {
+                self.route.remove(0);
+                target
+            } else {
+                BoardPoint {
+                    x: self.position.x + dx / remaining * distance,
+                    y: self.position.y + dy / remaining * distance,
+                }
+            };
+            if board.is_wall_at(new_point) {
+                self.route.clear();
+                return;
+            }
+            self.position = new_point;
+            distance -= remaining;
+            if distance <= 0.0 {
+                return;
+            }
+        }
+    }
+
+    /// Carries out `action`, which takes over from any route being walked
+    pub fn act(&mut self, action: Action, board: &Board) {
+        self.route.clear();
+        match action {
+            Action::MoveForward => self.move_forward(board),
+            Action::MoveBackward => self.move_backward(board),
+            Action::StrafeLeft => self.strafe_left(board),
+            Action::StrafeRight => self.strafe_right(board),
+            Action::TurnLeft => self.angle += self.angle_tick,
+            Action::TurnRight => self.angle -= self.angle_tick,
+            Action::StepUp => self.step(-1.0, board),
+            Action::StepDown => self.step(1.0, board),
         }
     }
 
@@ -111,6 +280,17 @@ impl Player {
         self.move_angle(perpendicular_angle, board);
     }
 
+    /// Teleports `rows` tiles down, through walls but never off the board
+    fn step(&mut self, rows: f64, board: &Board) {
+        let new_point = board.wrap(BoardPoint {
+            x: self.position.x,
+            y: self.position.y + rows,
+        });
+        if board.tile_at(new_point).is_some() {
+            self.position = new_point;
+        }
+    }
+
     fn move_angle(&mut self, angle: f64, board: &Board) {
         let (sin, cos) = angle.sin_cos();
         let new_point = BoardPoint {
@@ -120,7 +300,6 @@ impl Player {
         if board.is_wall_at(new_point) {
             return;
         }
-        self.position.x = new_point.x;
-        self.position.y = new_point.y;
+        self.position = board.wrap(new_point);
     }
 }
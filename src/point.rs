@@ -1,3 +1,4 @@
+#[derive(Debug, Clone, Copy)]
 pub struct ScreenPoint {
     pub x: f64,
     pub y: f64,
@@ -22,8 +23,7 @@ impl BoardPoint {
     pub fn get_distance(self, other_point: RayPoint) -> f64 {
         let dx = self.x - other_point.x;
         let dy = self.y - other_point.y;
-        let d = (dx.powf(2.0) + dy.powf(2.0)).sqrt();
-        d
+        (dx.powf(2.0) + dy.powf(2.0)).sqrt()
     }
     pub fn new() -> BoardPoint {
         BoardPoint { x: 0.0, y: 0.0 }
@@ -54,9 +54,9 @@ pub struct RayPoint {
 impl RayPoint {
     pub fn new(x: f64, y: f64, intercept_type: InterceptType) -> RayPoint {
         RayPoint {
-            x: x,
-            y: y,
-            intercept_type: intercept_type,
+            x,
+            y,
+            intercept_type,
             board_index: None,
             has_wall_intersection: false,
         }
@@ -65,8 +65,7 @@ impl RayPoint {
     pub fn get_distance(self, other_point: BoardPoint) -> f64 {
         let dx = self.x - other_point.x;
         let dy = self.y - other_point.y;
-        let d = (dx.powf(2.0) + dy.powf(2.0)).sqrt();
-        d
+        (dx.powf(2.0) + dy.powf(2.0)).sqrt()
     }
 }
 
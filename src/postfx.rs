@@ -0,0 +1,355 @@
+use frame::Frame;
+
+/// 4x4 Bayer matrix for ordered dithering
+const BAYER_4X4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Pass {
+    /// scales every channel by `brightness` after applying `gamma`
+    Gamma { gamma: f32, brightness: f32 },
+    /// averages `size` x `size` blocks into one colour
+    Pixelate { size: u32 },
+    /// ordered dithering down to `levels` values per channel
+    Dither { levels: u8 },
+    /// darkens every other row by `strength`
+    Scanlines { strength: f32 },
+    /// darkens towards the corners by up to `strength`
+    Vignette { strength: f32 },
+}
+
+impl Pass {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Pass::Gamma { .. } => "gamma",
+            Pass::Pixelate { .. } => "pixelate",
+            Pass::Dither { .. } => "dither",
+            Pass::Scanlines { .. } => "scanlines",
+            Pass::Vignette { .. } => "vignette",
+        }
+    }
+
+    /// `name[:param[:param]]`, e.g. `pixelate:4` or `gamma:2.2:1.1`
+    pub fn parse(spec: &str) -> Result<Pass, String> {
+        let mut parts = spec.split(':');
+        let name = parts.next().unwrap_or("");
+        let params = parts
+            .map(|part| part.parse::<f32>())
+            .collect::<Result<Vec<f32>, _>>()
+            .map_err(|_| format!("bad post process parameter in '{}'", spec))?;
+        let param = |i: usize, default: f32| params.get(i).cloned().unwrap_or(default);
+        let pass = match name {
+            "gamma" => {
+                let gamma = param(0, 2.2);
+                if gamma.is_nan() || gamma <= 0.0 {
+                    return Err(format!("gamma must be above 0 in '{}'", spec));
+                }
+                Pass::Gamma {
+                    gamma,
+                    brightness: param(1, 1.0),
+                }
+            }
+            "pixelate" => Pass::Pixelate {
+                size: param(0, 4.0).max(1.0) as u32,
+            },
+            "dither" => Pass::Dither {
+                levels: param(0, 4.0).clamp(2.0, 255.0) as u8,
+            },
+            "scanlines" => Pass::Scanlines {
+                strength: param(0, 0.35),
+            },
+            "vignette" => Pass::Vignette {
+                strength: param(0, 0.6),
+            },
+            _ => return Err(format!("unknown post process pass '{}'", name)),
+        };
+        Ok(pass)
+    }
+
+    pub fn apply(&self, frame: &mut Frame) {
+        match *self {
+            Pass::Gamma { gamma, brightness } => gamma_pass(frame, gamma, brightness),
+            Pass::Pixelate { size } => pixelate_pass(frame, size),
+            Pass::Dither { levels } => dither_pass(frame, levels),
+            Pass::Scanlines { strength } => scanlines_pass(frame, strength),
+            Pass::Vignette { strength } => vignette_pass(frame, strength),
+        }
+    }
+
+    /// Applies the pass to every colour of a palette, `false` and nothing changed
+    /// when the result depends on where a pixel is or on its neighbours
+    pub fn apply_palette(&self, pixels: &mut [[u8; 4]]) -> bool {
+        match *self {
+            Pass::Gamma { gamma, brightness } => {
+                let table = gamma_table(gamma, brightness);
+                for pixel in pixels.iter_mut() {
+                    for channel in pixel.iter_mut().take(3) {
+                        *channel = table[*channel as usize];
+                    }
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PostPass {
+    pub pass: Pass,
+    pub enabled: bool,
+}
+
+/// Ordered list of passes run over every rendered 3d frame
+#[derive(Debug, Clone)]
+pub struct PostChain {
+    pub passes: Vec<PostPass>,
+}
+
+impl Default for PostChain {
+    /// Every pass with default settings, all switched off
+    fn default() -> PostChain {
+        let passes = ["gamma", "pixelate", "dither", "scanlines", "vignette"]
+            .iter()
+            .map(|name| PostPass {
+                pass: Pass::parse(name).expect("default pass"),
+                enabled: false,
+            })
+            .collect();
+        PostChain { passes }
+    }
+}
+
+impl PostChain {
+    /// Comma separated passes, run in the given order and switched on,
+    /// e.g. `pixelate:3,dither,scanlines`
+    pub fn parse(spec: &str) -> Result<PostChain, String> {
+        let passes = spec
+            .split(',')
+            .filter(|part| !part.is_empty())
+            .map(|part| {
+                Pass::parse(part).map(|pass| PostPass {
+                    pass,
+                    enabled: true,
+                })
+            })
+            .collect::<Result<Vec<PostPass>, String>>()?;
+        Ok(PostChain { passes })
+    }
+
+    /// Switches every pass named `name` on or off, adding it with default
+    /// settings at the end of the chain when it isn't there yet
+    pub fn toggle(&mut self, name: &str) {
+        let mut found = false;
+        for post_pass in self.passes.iter_mut().filter(|p| p.pass.name() == name) {
+            post_pass.enabled = !post_pass.enabled;
+            found = true;
+        }
+        if !found {
+            if let Ok(pass) = Pass::parse(name) {
+                self.passes.push(PostPass {
+                    pass,
+                    enabled: true,
+                });
+            }
+        }
+    }
+
+    /// Runs the passes from `first` on, 0 for the whole chain
+    pub fn apply_from(&self, frame: &mut Frame, first: usize) {
+        for post_pass in self.passes.iter().skip(first).filter(|p| p.enabled) {
+            post_pass.pass.apply(frame);
+        }
+    }
+
+    /// Runs the leading passes that only change colours over a palette instead
+    /// of every pixel, returning how many passes it covered for `apply_from`
+    pub fn apply_to_palette(&self, pixels: &mut [[u8; 4]]) -> usize {
+        let mut done = 0;
+        for post_pass in self.passes.iter() {
+            if post_pass.enabled && !post_pass.pass.apply_palette(pixels) {
+                break;
+            }
+            done += 1;
+        }
+        done
+    }
+}
+
+impl std::fmt::Display for PostChain {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Post {{ ")?;
+        for post_pass in self.passes.iter() {
+            let state = if post_pass.enabled { "on" } else { "off" };
+            write!(f, "{}: {}, ", post_pass.pass.name(), state)?;
+        }
+        write!(f, "}}")
+    }
+}
+
+fn map_pixels<F: Fn(u32, u32, [u8; 4]) -> [u8; 4]>(frame: &mut Frame, f: F) {
+    for y in 0..frame.height() {
+        for x in 0..frame.width() {
+            let pixel = frame.get(x, y);
+            frame.set(x, y, f(x, y, pixel));
+        }
+    }
+}
+
+fn scale_rgb(pixel: [u8; 4], scale: f32) -> [u8; 4] {
+    let channel = |value: u8| (f32::from(value) * scale).round().clamp(0.0, 255.0) as u8;
+    [
+        channel(pixel[0]),
+        channel(pixel[1]),
+        channel(pixel[2]),
+        pixel[3],
+    ]
+}
+
+fn gamma_table(gamma: f32, brightness: f32) -> [u8; 256] {
+    let mut table = [0u8; 256];
+    for (value, entry) in table.iter_mut().enumerate() {
+        let corrected = (value as f32 / 255.0).powf(1.0 / gamma) * brightness;
+        *entry = (corrected * 255.0).round().clamp(0.0, 255.0) as u8;
+    }
+    table
+}
+
+fn gamma_pass(frame: &mut Frame, gamma: f32, brightness: f32) {
+    let table = gamma_table(gamma, brightness);
+    map_pixels(frame, |_, _, pixel| {
+        [
+            table[pixel[0] as usize],
+            table[pixel[1] as usize],
+            table[pixel[2] as usize],
+            pixel[3],
+        ]
+    });
+}
+
+fn pixelate_pass(frame: &mut Frame, size: u32) {
+    if size <= 1 {
+        return;
+    }
+    let (width, height) = (frame.width(), frame.height());
+    for block_y in (0..height).step_by(size as usize) {
+        for block_x in (0..width).step_by(size as usize) {
+            let (right, bottom) = ((block_x + size).min(width), (block_y + size).min(height));
+            let mut sum = [0u32; 4];
+            for y in block_y..bottom {
+                for x in block_x..right {
+                    for (total, &channel) in sum.iter_mut().zip(frame.get(x, y).iter()) {
+                        *total += u32::from(channel);
+                    }
+                }
+            }
+            let count = (right - block_x) * (bottom - block_y);
+            let average = [
+                (sum[0] / count) as u8,
+                (sum[1] / count) as u8,
+                (sum[2] / count) as u8,
+                (sum[3] / count) as u8,
+            ];
+            for y in block_y..bottom {
+                for x in block_x..right {
+                    frame.set(x, y, average);
+                }
+            }
+        }
+    }
+}
+
+fn dither_pass(frame: &mut Frame, levels: u8) {
+    let steps = f32::from(levels - 1);
+    map_pixels(frame, |x, y, pixel| {
+        let threshold = (f32::from(BAYER_4X4[(y % 4) as usize][(x % 4) as usize]) + 0.5) / 16.0;
+        let channel = |value: u8| {
+            let scaled = f32::from(value) / 255.0 * steps;
+            let level = if scaled.fract() > threshold {
+                scaled.ceil()
+            } else {
+                scaled.floor()
+            };
+            (level / steps * 255.0).round() as u8
+        };
+        [
+            channel(pixel[0]),
+            channel(pixel[1]),
+            channel(pixel[2]),
+            pixel[3],
+        ]
+    });
+}
+
+fn scanlines_pass(frame: &mut Frame, strength: f32) {
+    map_pixels(frame, |_, y, pixel| {
+        if y % 2 == 1 {
+            scale_rgb(pixel, 1.0 - strength)
+        } else {
+            pixel
+        }
+    });
+}
+
+fn vignette_pass(frame: &mut Frame, strength: f32) {
+    let center_x = frame.width() as f32 / 2.0;
+    let center_y = frame.height() as f32 / 2.0;
+    let max_distance = (center_x * center_x + center_y * center_y).sqrt();
+    map_pixels(frame, |x, y, pixel| {
+        let dx = x as f32 + 0.5 - center_x;
+        let dy = y as f32 + 0.5 - center_y;
+        let distance = (dx * dx + dy * dy).sqrt() / max_distance;
+        scale_rgb(pixel, 1.0 - strength * distance * distance)
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(chain: &PostChain) -> Vec<(&'static str, bool)> {
+        chain
+            .passes
+            .iter()
+            .map(|p| (p.pass.name(), p.enabled))
+            .collect()
+    }
+
+    #[test]
+    fn toggle_finds_the_pass_by_name_in_a_custom_chain() {
+        let mut chain = PostChain::parse("scanlines,pixelate:3").unwrap();
+        chain.toggle("pixelate");
+        assert_eq!(
+            state(&chain),
+            vec![("scanlines", true), ("pixelate", false)]
+        );
+        chain.toggle("pixelate");
+        assert_eq!(chain.passes[1].pass, Pass::Pixelate { size: 3 });
+        assert!(chain.passes[1].enabled);
+    }
+
+    #[test]
+    fn palette_passes_match_running_them_over_the_frame() {
+        let chain = PostChain::parse("gamma:1.8:1.2,scanlines,gamma").unwrap();
+        let colors = [[10, 200, 30, 255], [255, 0, 128, 255], [90, 90, 90, 255]];
+        let mut pixels = colors;
+        assert_eq!(chain.apply_to_palette(&mut pixels), 1);
+
+        let mut frame = Frame::new(colors.len() as u32, 1);
+        for (x, &color) in colors.iter().enumerate() {
+            frame.set(x as u32, 0, color);
+        }
+        chain.passes[0].pass.apply(&mut frame);
+        for (x, &pixel) in pixels.iter().enumerate() {
+            assert_eq!(frame.get(x as u32, 0), pixel);
+        }
+    }
+
+    #[test]
+    fn toggle_appends_a_pass_left_out_of_the_chain() {
+        let mut chain = PostChain::parse("dither").unwrap();
+        chain.toggle("vignette");
+        assert_eq!(state(&chain), vec![("dither", true), ("vignette", true)]);
+        chain.toggle("vignette");
+        assert_eq!(state(&chain), vec![("dither", true), ("vignette", false)]);
+    }
+}
@@ -1,10 +1,24 @@
-use board::Board;
+use board::{Board, OutOfBounds};
 use colors;
 use display_vec::DisplayVec;
 use graphics::{math::Matrix2d, Transformed};
 use point::{BoardPoint, InterceptType, RayPoint};
 use sharp_graphics::SharpGraphics;
 
+/// Tiles an intercept can be off a grid corner and still count as on it, far
+/// below a pixel so only float error is forgiven
+const GRID_EPSILON: f64 = 1e-9;
+
+/// What a ray finds where it crosses a grid line
+enum Crossing {
+    /// a tile on the board, wrapped back onto it under `OutOfBounds::Wrap`
+    Tile(usize),
+    /// open floor past the edge under `OutOfBounds::Empty`
+    Open,
+    /// past the edge, with nothing left the ray could reach
+    End,
+}
+
 #[derive(Debug, Clone)]
 pub struct Ray {
     pub angle: f64,
@@ -50,20 +64,22 @@ impl Ray {
         self.angle = angle;
         let (sin, cos) = self.angle.sin_cos();
         self.x_intercepts = self.get_x_intercepts(board, sin, cos);
-        self.y_intercepts = self.get_y_intercepts(board, cos);
+        self.y_intercepts = self.get_y_intercepts(board, sin, cos);
         let (wall_intersection, wall_distance) = self.get_wall_intersection();
         self.wall_intersection = wall_intersection;
         self.wall_distance = wall_distance;
+        self.wall_height = Ray::height_at(wall_distance);
+    }
 
+    /// Height in the reference view of a full wall `distance` pixels away
+    pub fn height_at(distance: f64) -> f64 {
         const DELTA_SIZE: f64 = 10.0;
         const INITIAL_SIZE: f64 = 1000.0;
-        let fraction = DELTA_SIZE / wall_distance;
-        self.wall_height = fraction * INITIAL_SIZE;
+        let fraction = DELTA_SIZE / distance;
+        fraction * INITIAL_SIZE
     }
 
-    pub fn draw(&self, transform: Matrix2d, graphics: &mut SharpGraphics, block_size: f64) {
-        // self._draw_intercepts(transform, graphics);
-
+    pub fn draw_fan(&self, transform: Matrix2d, graphics: &mut SharpGraphics, block_size: f64) {
         if let Some(point) = self.wall_intersection {
             graphics.draw_line(
                 colors::YELLOW,
@@ -75,6 +91,11 @@ impl Ray {
                 ],
                 transform,
             );
+        }
+    }
+
+    pub fn draw_hit(&self, transform: Matrix2d, graphics: &mut SharpGraphics) {
+        if let Some(point) = self.wall_intersection {
             let color = if point.intercept_type == InterceptType::XIntercept {
                 colors::RED_ALPHA
             } else {
@@ -95,7 +116,7 @@ impl Ray {
             .iter()
             .filter(|p| p.has_wall_intersection)
             .collect();
-        if x_intersections.len() == 0 && y_intersections.len() == 0 {
+        if x_intersections.is_empty() && y_intersections.is_empty() {
             return (None, 0.0);
         }
 
@@ -105,11 +126,11 @@ impl Ray {
             y: self.start_position.y * 50.0,
         };
 
-        if x_intersections.len() == 0 {
+        if x_intersections.is_empty() {
             let point = **y_intersections.last().unwrap();
             return (Some(point), point.get_distance(player_point));
         }
-        if y_intersections.len() == 0 {
+        if y_intersections.is_empty() {
             let point = **x_intersections.last().unwrap();
             return (Some(point), point.get_distance(player_point));
         }
@@ -120,9 +141,9 @@ impl Ray {
         let y_distance = player_point.get_distance(y_intercept);
 
         if x_distance < y_distance {
-            return (Some(x_intercept), x_distance);
+            (Some(x_intercept), x_distance)
         } else {
-            return (Some(y_intercept), y_distance);
+            (Some(y_intercept), y_distance)
         }
     }
 
@@ -131,135 +152,118 @@ impl Ray {
         let mut x_intercept = self.get_initial_x_intercept(board.block_size, sin, x_tan);
         let mut x_intercepts = DisplayVec::<RayPoint>::new();
 
-        if let Some(board_index) = Ray::get_board_index_x(board, x_intercept, sin, cos) {
-            if board.tiles[board_index] != 0 {
-                x_intercept.has_wall_intersection = true;
-                x_intercept.board_index = Some(board_index);
-                x_intercepts.push(x_intercept);
-                return x_intercepts;
-            } else {
-                x_intercept.board_index = Some(board_index);
-                x_intercepts.push(x_intercept);
-            }
-        } else {
-            return x_intercepts;
-        }
-
-        for _ in 0..board.tiles_y {
-            x_intercept = self.get_x_intercept(board.block_size, x_intercept, sin, x_tan);
-            if let Some(board_index) = Ray::get_board_index_x(board, x_intercept, sin, cos) {
-                if board.tiles[board_index] != 0 {
-                    x_intercept.has_wall_intersection = true;
-                    x_intercept.board_index = Some(board_index);
-                    x_intercepts.push(x_intercept);
-                    return x_intercepts;
-                } else {
+        for _ in 0..Ray::max_crossings(board, board.tiles_y) {
+            match Ray::get_board_index_x(board, x_intercept, sin, cos) {
+                Crossing::Tile(board_index) => {
                     x_intercept.board_index = Some(board_index);
+                    x_intercept.has_wall_intersection = board
+                        .tile(board_index)
+                        .is_some_and(|tile| board.blocks_sight(tile));
                     x_intercepts.push(x_intercept);
+                    if x_intercept.has_wall_intersection {
+                        return x_intercepts;
+                    }
                 }
-            } else {
-                return x_intercepts;
+                Crossing::Open => {}
+                Crossing::End => return x_intercepts,
             }
+            x_intercept = self.get_x_intercept(board.block_size, x_intercept, sin, x_tan);
         }
 
         x_intercepts
     }
 
-    fn get_y_intercepts(&self, board: &Board, cos: f64) -> DisplayVec<RayPoint> {
+    fn get_y_intercepts(&self, board: &Board, sin: f64, cos: f64) -> DisplayVec<RayPoint> {
         let y_tan = self.angle.tan();
         let mut y_intercept = self.get_initial_y_intercept(board.block_size, cos, y_tan);
         let mut y_intercepts = DisplayVec::<RayPoint>::new();
 
-        if let Some(board_index) = Ray::get_board_index_y(board, y_intercept, cos) {
-            if board.tiles[board_index] != 0 {
-                y_intercept.has_wall_intersection = true;
-                y_intercept.board_index = Some(board_index);
-                y_intercepts.push(y_intercept);
-                return y_intercepts;
-            } else {
-                y_intercept.board_index = Some(board_index);
-                y_intercepts.push(y_intercept);
-            }
-        } else {
-            return y_intercepts;
-        }
-
-        for _ in 0..board.tiles_x {
-            y_intercept = self.get_y_intercept(board.block_size, y_intercept, cos, y_tan);
-            if let Some(board_index) = Ray::get_board_index_y(board, y_intercept, cos) {
-                if board.tiles[board_index] != 0 {
-                    y_intercept.has_wall_intersection = true;
-                    y_intercept.board_index = Some(board_index);
-                    y_intercepts.push(y_intercept);
-                    return y_intercepts;
-                } else {
+        for _ in 0..Ray::max_crossings(board, board.tiles_x) {
+            match Ray::get_board_index_y(board, y_intercept, sin, cos) {
+                Crossing::Tile(board_index) => {
                     y_intercept.board_index = Some(board_index);
+                    y_intercept.has_wall_intersection = board
+                        .tile(board_index)
+                        .is_some_and(|tile| board.blocks_sight(tile));
                     y_intercepts.push(y_intercept);
+                    if y_intercept.has_wall_intersection {
+                        return y_intercepts;
+                    }
                 }
-            } else {
-                return y_intercepts;
+                Crossing::Open => {}
+                Crossing::End => return y_intercepts,
             }
+            y_intercept = self.get_y_intercept(board.block_size, y_intercept, cos, y_tan);
         }
 
         y_intercepts
     }
 
-    fn get_board_index_x(
-        board: &Board,
-        x_intercept: RayPoint,
-        sin: f64,
-        cos: f64,
-    ) -> Option<usize> {
-        if x_intercept.x < 0.0 {
-            return None;
-        }
-        let rounded_x_intercept = x_intercept.x.round();
-        if rounded_x_intercept >= board.tiles_x as f64 * board.block_size {
-            return None;
+    fn get_board_index_x(board: &Board, x_intercept: RayPoint, sin: f64, cos: f64) -> Crossing {
+        let (x_tiles, on_corner) = Ray::grid_position(x_intercept.x / board.block_size);
+        let mut x_tile = x_tiles.floor() as i64;
+        // exactly on a corner heading left, the wall is in the tile to the left
+        if on_corner && cos < 0.0 {
+            x_tile -= 1;
         }
-        let x_tile_float = rounded_x_intercept / board.block_size;
-        let mut x_tile = x_tile_float.floor() as usize;
-
-        // if the rounded intercept is a tile intersection, use cosine to estimate most accurate tile
-        // todo: may need to be done with y intercept as well, we'll see
-        if x_tile_float % 1.0 == 0.0 && cos < 0.0 {
-            if x_tile == 0 {
-                return None;
-            }
-            x_tile -= 1
+        let mut y_tile = (x_intercept.y / board.block_size).round() as i64;
+        if sin < 0.0 {
+            y_tile -= 1;
         }
+        Ray::crossing(board, x_tile, y_tile, sin, cos)
+    }
 
-        let mut y_tile = (x_intercept.y / board.block_size) as usize;
-        if sin < 0.0 {
-            if y_tile == 0 {
-                //todo: check seems unnecessary when inside board since this is for x intercept
-                return None;
-            }
+    fn get_board_index_y(board: &Board, y_intercept: RayPoint, sin: f64, cos: f64) -> Crossing {
+        let (y_tiles, on_corner) = Ray::grid_position(y_intercept.y / board.block_size);
+        let mut y_tile = y_tiles.floor() as i64;
+        // exactly on a corner heading up, the wall is in the tile above
+        if on_corner && sin < 0.0 {
             y_tile -= 1;
         }
-        if y_tile >= board.tiles_y {
-            //todo: check seems unnecessary when inside board since this is for x intercept
-            return None;
+        let mut x_tile = (y_intercept.x / board.block_size).round() as i64;
+        if cos < 0.0 {
+            x_tile -= 1;
         }
-        let index = board.get_index_from_tile(x_tile, y_tile);
-        Some(index)
+        Ray::crossing(board, x_tile, y_tile, sin, cos)
     }
 
-    fn get_board_index_y(board: &Board, y_intercept: RayPoint, cos: f64) -> Option<usize> {
-        let mut x_tile = (y_intercept.x / board.block_size) as usize;
-        let y_tile = (y_intercept.y.round() / board.block_size).floor() as usize;
-        if cos < 0.0 {
-            if x_tile == 0 {
-                return None;
+    /// Tile (`x_tile`, `y_tile`) as the board's `out_of_bounds` policy has a ray
+    /// see it, past the edge a ray still heading for the board carries on
+    fn crossing(board: &Board, x_tile: i64, y_tile: i64, sin: f64, cos: f64) -> Crossing {
+        if let Some(board_index) = board.wrapped_index(x_tile, y_tile) {
+            return Crossing::Tile(board_index);
+        }
+        let leaving = |tile: i64, size: usize, delta: f64| {
+            (tile < 0 && delta <= 0.0) || (tile >= size as i64 && delta >= 0.0)
+        };
+        match board.out_of_bounds {
+            OutOfBounds::Empty
+                if !leaving(x_tile, board.tiles_x, cos) && !leaving(y_tile, board.tiles_y, sin) =>
+            {
+                Crossing::Open
             }
-            x_tile -= 1;
+            _ => Crossing::End,
         }
-        if x_tile >= board.tiles_x || y_tile >= board.tiles_y {
-            return None;
+    }
+
+    /// Grid lines a ray checks at most, only wrapping rays never leave the board
+    /// so they give up after crossing it twice
+    fn max_crossings(board: &Board, lines: usize) -> usize {
+        match board.out_of_bounds {
+            OutOfBounds::Wrap => 2 * lines + 1,
+            _ => usize::MAX,
         }
+    }
 
-        let index = board.get_index_from_tile(x_tile, y_tile);
-        Some(index)
+    /// Position along a grid line in tiles, snapped onto a corner when float error
+    /// is all that keeps it off one, and whether it sits on a corner
+    fn grid_position(tiles: f64) -> (f64, bool) {
+        let corner = tiles.round();
+        if (tiles - corner).abs() <= GRID_EPSILON {
+            (corner, true)
+        } else {
+            (tiles, false)
+        }
     }
 
     fn get_initial_x_intercept(&self, block_size: f64, sin: f64, x_tan: f64) -> RayPoint {
@@ -346,7 +350,7 @@ impl Ray {
         graphics.draw_rectangle(color, [0.0, 0.0, 10.0, 10.0], xform);
     }
 
-    fn _draw_intercepts(&self, transform: Matrix2d, graphics: &mut SharpGraphics) {
+    pub fn draw_intercepts(&self, transform: Matrix2d, graphics: &mut SharpGraphics) {
         for &x_intercept in self.x_intercepts.iter() {
             self.draw_intercept(transform, graphics, x_intercept, colors::RED_ALPHA);
         }
@@ -356,3 +360,94 @@ impl Ray {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use map;
+    use map::picture::Legend;
+
+    fn default_board() -> Board {
+        map::load("assets/maps/default.txt", 50.0, &Legend::default())
+            .unwrap()
+            .board
+    }
+
+    /// First wall along a ray found by walking the grid one crossing at a time,
+    /// `None` inside the outer `Option` when the ray passes a corner so closely
+    /// that either neighbour could be right
+    fn grid_walk(board: &Board, start: BoardPoint, angle: f64) -> Option<Option<usize>> {
+        let (sin, cos) = angle.sin_cos();
+        let (mut x, mut y) = (start.x.floor() as i64, start.y.floor() as i64);
+        let (step_x, step_y) = (
+            if cos > 0.0 { 1 } else { -1 },
+            if sin > 0.0 { 1 } else { -1 },
+        );
+        let first = |start: f64, tile: i64, delta: f64| {
+            if delta > 0.0 {
+                (tile as f64 + 1.0 - start) / delta
+            } else if delta < 0.0 {
+                (tile as f64 - start) / delta
+            } else {
+                f64::INFINITY
+            }
+        };
+        let (mut next_x, mut next_y) = (first(start.x, x, cos), first(start.y, y, sin));
+        let (delta_x, delta_y) = (1.0 / cos.abs(), 1.0 / sin.abs());
+        loop {
+            if (next_x - next_y).abs() < 1e-6 {
+                return None;
+            }
+            if next_x < next_y {
+                x += step_x;
+                next_x += delta_x;
+            } else {
+                y += step_y;
+                next_y += delta_y;
+            }
+            if x < 0 || y < 0 || x >= board.tiles_x as i64 || y >= board.tiles_y as i64 {
+                return Some(None);
+            }
+            let index = y as usize * board.tiles_x + x as usize;
+            if board.tiles[index] != 0 {
+                return Some(Some(index));
+            }
+        }
+    }
+
+    fn hit_tile(board: &Board, start: BoardPoint, angle: f64) -> Option<usize> {
+        let mut ray = Ray::new();
+        ray.update(start, angle, board);
+        ray.wall_intersection.and_then(|point| point.board_index)
+    }
+
+    #[test]
+    fn rays_hit_the_wall_a_grid_walk_finds() {
+        let board = default_board();
+        let offsets = [0.1, 0.5, 0.83];
+        for (index, _) in board.tiles.iter().enumerate().filter(|&(_, &t)| t == 0) {
+            let tile = board.get_tile(index);
+            for &dx in offsets.iter() {
+                for &dy in offsets.iter() {
+                    let start = BoardPoint {
+                        x: tile.x + dx,
+                        y: tile.y + dy,
+                    };
+                    for step in 0..360 {
+                        let angle = (step as f64 + 0.25).to_radians();
+                        if let Some(expected) = grid_walk(&board, start, angle) {
+                            assert_eq!(
+                                hit_tile(&board, start, angle),
+                                expected,
+                                "from ({}, {}) at {} degrees",
+                                start.x,
+                                start.y,
+                                step as f64 + 0.25
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,408 @@
+use animation::TileFrame;
+use board::{Board, BoardLayer};
+use colors;
+use decal::{DecalColumn, Face, WallHit};
+use frame::Frame;
+use map::document::from_rgba;
+use palette::{IndexedColors, IndexedFrame};
+use point::{BoardPoint, InterceptType, RayPoint};
+use postfx::PostChain;
+use ray::Ray;
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use stereo::StereoMode;
+
+/// Wall heights from `Ray::update` are sized for a 3d view this tall
+pub const REFERENCE_VIEW_HEIGHT: f64 = 300.0;
+
+/// Settings shared by every camera for one rendered frame
+pub struct RenderOptions<'a> {
+    pub stereo_mode: StereoMode,
+    pub post: &'a PostChain,
+    /// render through the palette and colour map instead of RGBA
+    pub indexed: Option<&'a IndexedColors>,
+}
+
+/// One wall face seen by a ray, the one it stopped at or one it saw past
+struct WallSlice {
+    point: RayPoint,
+    face: Face,
+    tile: u32,
+    distance: f64,
+    /// height of a full wall this far away in the reference view
+    wall_height: f64,
+}
+
+impl WallSlice {
+    /// The wall `ray` stopped at
+    fn hit(ray: &Ray, board: &Board) -> Option<WallSlice> {
+        let point = ray.wall_intersection?;
+        let tile = board.tile(point.board_index?)?;
+        Some(WallSlice {
+            point,
+            face: Face::seen_along(point.intercept_type, ray.angle),
+            tile: tile.id(),
+            distance: ray.wall_distance,
+            wall_height: ray.wall_height,
+        })
+    }
+
+    /// Transparent or low walls `ray` saw past on the way to its hit, furthest first
+    fn see_through(ray: &Ray, board: &Board) -> Vec<WallSlice> {
+        let start = BoardPoint {
+            x: ray.start_position.x * board.block_size,
+            y: ray.start_position.y * board.block_size,
+        };
+        let mut slices: Vec<WallSlice> = ray
+            .x_intercepts
+            .iter()
+            .chain(ray.y_intercepts.iter())
+            .filter(|point| !point.has_wall_intersection)
+            .filter_map(|&point| {
+                let tile = board.tile(point.board_index?)?;
+                if tile.is_empty() {
+                    return None;
+                }
+                let distance = point.get_distance(start);
+                Some(WallSlice {
+                    point,
+                    face: Face::seen_along(point.intercept_type, ray.angle),
+                    tile: tile.id(),
+                    distance,
+                    wall_height: Ray::height_at(distance),
+                })
+            })
+            .filter(|slice| ray.wall_intersection.is_none() || slice.distance < ray.wall_distance)
+            .collect();
+        slices.sort_by(|a, b| b.distance.total_cmp(&a.distance));
+        slices
+    }
+}
+
+/// Top and bottom rows of a wall column in a view `height` pixels tall, for a
+/// wall `tile_height` of a full one standing on the floor
+fn wall_span(wall_height: f64, height: u32, tile_height: f64) -> (u32, u32) {
+    let (top, length) = wall_extent(wall_height, height, tile_height);
+    let bottom = (top + length).min(f64::from(height)).round() as u32;
+    ((top.max(0.0).round() as u32).min(bottom), bottom)
+}
+
+/// Unclipped top row and height of a wall column, for mapping rows to `0.0..1.0`
+/// down the wall
+fn wall_extent(wall_height: f64, height: u32, tile_height: f64) -> (f64, f64) {
+    let full = wall_height * f64::from(height) / REFERENCE_VIEW_HEIGHT;
+    let length = full * tile_height;
+    ((f64::from(height) + full) / 2.0 - length, length)
+}
+
+/// Decals on the wall `ray` hit, `None` when there are none to draw
+fn decal_column<'a>(ray: &Ray, board: &'a Board) -> Option<DecalColumn<'a>> {
+    if board.decals.is_empty() {
+        return None;
+    }
+    let hit = WallHit::from_ray(ray, board.block_size)?;
+    Some(board.decals.column(&hit)).filter(|column| !column.is_empty())
+}
+
+/// Whether any floor or ceiling tile has a look of its own
+fn has_surfaces(board: &Board) -> bool {
+    [BoardLayer::Floor, BoardLayer::Ceiling]
+        .iter()
+        .any(|&layer| board.layer(layer).iter().any(|&id| id != 0))
+}
+
+/// Calls `paint(row, id, distance)` for every row of a view `height` pixels
+/// tall where `ray` looks down at a floor tile or up at a ceiling tile, leaving
+/// out the rows of the wall it hit and ids of 0
+///
+/// The distance is `None` for sky on the ceiling, which is infinitely far away.
+fn cast_surfaces(
+    ray: &Ray,
+    board: &Board,
+    height: u32,
+    mut paint: impl FnMut(u32, u32, Option<f64>),
+) {
+    let first = match ray.wall_intersection {
+        Some(_) => wall_span(ray.wall_height, height, 1.0).1,
+        None => 0,
+    }
+    .max(height / 2);
+    let (sin, cos) = ray.angle.sin_cos();
+    for y in first..height {
+        // a full wall whose bottom edge runs through the middle of this row
+        let full = f64::from(2 * y + 1) - f64::from(height);
+        if full <= 0.0 {
+            continue;
+        }
+        // `height_at` is a constant over the distance, so it also turns a
+        // height back into a distance
+        let distance = Ray::height_at(full * REFERENCE_VIEW_HEIGHT / f64::from(height));
+        let point = board.wrap(BoardPoint {
+            x: ray.start_position.x + cos * distance / board.block_size,
+            y: ray.start_position.y + sin * distance / board.block_size,
+        });
+        if let Some(id) = board
+            .layer_at(BoardLayer::Floor, point)
+            .filter(|&id| id != 0)
+        {
+            paint(y, id, Some(distance));
+        }
+        // walls are centred on the horizon, so the ceiling row mirrors the floor row
+        if let Some(id) = board
+            .layer_at(BoardLayer::Ceiling, point)
+            .filter(|&id| id != 0)
+        {
+            let distance = Some(distance).filter(|_| !board.is_sky(id));
+            paint(height - 1 - y, id, distance);
+        }
+    }
+}
+
+/// Draws ceiling, floor and one wall column per ray into the `width` columns of
+/// `frame` starting at `left`
+pub fn render_view(frame: &mut Frame, left: u32, width: u32, rays: &[Ray], board: &Board) {
+    let height = frame.height();
+    let height_half = height / 2;
+    let right = left + width;
+    // 3d ceil
+    frame.fill_rect(left, 0, right, height_half, board.info.ceiling);
+    // 3d floor
+    frame.fill_rect(left, height_half, right, height, board.info.floor);
+    let surfaces = has_surfaces(board);
+
+    // floor and ceiling tiles, the 3d wall, then whatever the ray saw through in front of it
+    for (i, ray) in rays.iter().take(width as usize).enumerate() {
+        let x = left + i as u32;
+        if surfaces {
+            cast_surfaces(ray, board, height, |y, id, distance| {
+                let color = match distance {
+                    Some(_) => board.wall_color(id),
+                    None => sky_color(board, id, ray.angle, y, height),
+                };
+                if let Some(color) = color {
+                    frame.blend(x, y, color);
+                }
+            });
+        }
+        if let Some(slice) = WallSlice::hit(ray, board) {
+            draw_wall(frame, x, &slice, board, decal_column(ray, board));
+        }
+        for slice in WallSlice::see_through(ray, board).iter() {
+            draw_wall(frame, x, slice, board, None);
+        }
+    }
+}
+
+/// Colour of sky tile `id` at row `y` of a view `height` pixels tall, looking
+/// along `angle`
+fn sky_color(board: &Board, id: u32, angle: f64, y: u32, height: u32) -> Option<[f32; 4]> {
+    match board.wall_frame(id) {
+        Some(frame) => {
+            let (u, v) = sky_uv(angle, y, height);
+            Some(frame.sample(u, v))
+        }
+        None => board.wall_color(id),
+    }
+}
+
+/// Where row `y` looking along `angle` lands on a sky texture, which wraps once
+/// around the horizon and covers the top half of the view
+fn sky_uv(angle: f64, y: u32, height: u32) -> (f64, f64) {
+    let u = angle.rem_euclid(PI * 2.0) / (PI * 2.0);
+    let v = (f64::from(y) + 0.5) / f64::from((height / 2).max(1));
+    (u, v)
+}
+
+fn draw_wall(
+    frame: &mut Frame,
+    x: u32,
+    slice: &WallSlice,
+    board: &Board,
+    decals: Option<DecalColumn>,
+) {
+    let height = frame.height();
+    let tile_height = board.tile_height(slice.tile);
+    let (top, bottom) = wall_span(slice.wall_height, height, tile_height);
+    // v runs over the whole wall, including rows cut off by the view
+    let (wall_top, wall_length) = wall_extent(slice.wall_height, height, tile_height);
+    let v = |y: u32| (f64::from(y) + 0.5 - wall_top) / wall_length;
+    match board.wall_frame(slice.tile) {
+        Some(texture @ TileFrame::Texture { .. }) => {
+            let u = slice.face.offset(&slice.point, board.block_size);
+            for y in top..bottom.min(height) {
+                frame.blend(x, y, texture.sample(u, v(y)));
+            }
+        }
+        _ => {
+            let color = board.wall_color(slice.tile).unwrap_or(colors::UNKNOWN_TILE);
+            frame.fill_rect(x, top, x + 1, bottom, color);
+        }
+    }
+    if let Some(decals) = decals {
+        for y in top..bottom.min(height) {
+            if let Some(color) = decals.sample(v(y)) {
+                frame.blend(x, y, color);
+            }
+        }
+    }
+    if let Some(fog) = board.info.fog {
+        let [r, g, b, _] = from_rgba(fog.color);
+        let amount = fog.amount(slice.distance / board.block_size);
+        frame.fill_rect(x, top, x + 1, bottom, [r, g, b, amount]);
+    }
+}
+
+/// Palette entries looked up while drawing one indexed frame, textures have
+/// their own from `Board::index_textures`
+#[derive(Default)]
+struct IndexCache {
+    /// flat tile colours, which animations don't change within a frame
+    tiles: HashMap<u32, u8>,
+    /// decal texels, which only use a handful of colours
+    decals: HashMap<[u8; 3], u8>,
+}
+
+impl IndexCache {
+    fn tile(&mut self, tile: u32, board: &Board, colors: &IndexedColors) -> u8 {
+        *self.tiles.entry(tile).or_insert_with(|| {
+            let color = board.wall_color(tile).unwrap_or(colors::UNKNOWN_TILE);
+            colors.color_index(color)
+        })
+    }
+
+    fn decal(&mut self, color: [f32; 4], colors: &IndexedColors) -> u8 {
+        let key = [
+            (color[0] * 255.0) as u8,
+            (color[1] * 255.0) as u8,
+            (color[2] * 255.0) as u8,
+        ];
+        *self
+            .decals
+            .entry(key)
+            .or_insert_with(|| colors.color_index(color))
+    }
+}
+
+/// Indexed counterpart of `render_view`, walls are lit by distance through the
+/// colour map and fogged through the fog colour map
+pub fn render_view_indexed(
+    frame: &mut IndexedFrame,
+    left: u32,
+    width: u32,
+    rays: &[Ray],
+    board: &Board,
+    colors: &IndexedColors,
+) {
+    let height = frame.height();
+    let height_half = height / 2;
+    let mut cache = IndexCache::default();
+    let surfaces = has_surfaces(board);
+    let ceiling = colors.color_index(board.info.ceiling);
+    let floor = colors.color_index(board.info.floor);
+    for x in left..left + width {
+        frame.fill_column(x, 0, height_half, ceiling);
+        frame.fill_column(x, height_half, height, floor);
+
+        let ray = match rays.get((x - left) as usize) {
+            Some(ray) => ray,
+            None => continue,
+        };
+        if surfaces {
+            cast_surfaces(ray, board, height, |y, id, distance| {
+                if board.wall_color(id).is_none() {
+                    return;
+                }
+                let index = match distance {
+                    Some(distance) => {
+                        let level = colors.wall_level(distance / board.block_size, false);
+                        colors.color_map.shade(cache.tile(id, board, colors), level)
+                    }
+                    // the sky is as bright however far away it is
+                    None => match board.wall_frame(id) {
+                        Some(texture @ TileFrame::Texture { .. }) => {
+                            let (u, v) = sky_uv(ray.angle, y, height);
+                            match texture.sample_index(u, v) {
+                                Some(index) => index,
+                                None => return,
+                            }
+                        }
+                        _ => cache.tile(id, board, colors),
+                    },
+                };
+                frame.fill_column(x, y, y + 1, index);
+            });
+        }
+        if let Some(slice) = WallSlice::hit(ray, board) {
+            let decals = decal_column(ray, board);
+            draw_wall_indexed(frame, x, &slice, board, colors, &mut cache, decals);
+        }
+        for slice in WallSlice::see_through(ray, board).iter() {
+            draw_wall_indexed(frame, x, slice, board, colors, &mut cache, None);
+        }
+    }
+}
+
+/// Indexed counterpart of `draw_wall`, indexed pixels can't blend so mostly
+/// transparent walls, texels and decals are left out
+fn draw_wall_indexed(
+    frame: &mut IndexedFrame,
+    x: u32,
+    slice: &WallSlice,
+    board: &Board,
+    colors: &IndexedColors,
+    cache: &mut IndexCache,
+    decals: Option<DecalColumn>,
+) {
+    let height = frame.height();
+    let tile_height = board.tile_height(slice.tile);
+    let (top, bottom) = wall_span(slice.wall_height, height, tile_height);
+    let (wall_top, wall_length) = wall_extent(slice.wall_height, height, tile_height);
+    let v = |y: u32| (f64::from(y) + 0.5 - wall_top) / wall_length;
+    let east_west = slice.point.intercept_type == InterceptType::YIntercept;
+    let level = colors.wall_level(slice.distance / board.block_size, east_west);
+    let shade = |index: u8| shade_wall(index, level, slice.distance, board, colors);
+    match board.wall_frame(slice.tile) {
+        Some(texture @ TileFrame::Texture { .. }) => {
+            let u = slice.face.offset(&slice.point, board.block_size);
+            for y in top..bottom.min(height) {
+                if let Some(index) = texture.sample_index(u, v(y)) {
+                    frame.fill_column(x, y, y + 1, shade(index));
+                }
+            }
+        }
+        _ => {
+            if board
+                .wall_color(slice.tile)
+                .is_some_and(|color| color[3] < 0.5)
+            {
+                return;
+            }
+            let index = shade(cache.tile(slice.tile, board, colors));
+            frame.fill_column(x, top, bottom, index);
+        }
+    }
+    if let Some(decals) = decals {
+        for y in top..bottom.min(height) {
+            match decals.sample(v(y)) {
+                Some(color) if color[3] >= 0.5 => {
+                    let index = cache.decal(color, colors);
+                    frame.fill_column(x, y, y + 1, shade(index));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Lights `index` at `level`, then fades it into the map's fog for a wall
+/// `distance` pixels away
+fn shade_wall(index: u8, level: usize, distance: f64, board: &Board, colors: &IndexedColors) -> u8 {
+    let lit = colors.color_map.shade(index, level);
+    match (board.info.fog, colors.fog_map.as_ref()) {
+        (Some(fog), Some(fog_map)) => {
+            let amount = fog.amount(distance / board.block_size);
+            fog_map.shade(lit, colors.fog_level(amount))
+        }
+        _ => lit,
+    }
+}
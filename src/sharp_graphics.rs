@@ -1,17 +1,32 @@
+use frame::Frame;
 use graphics;
-use graphics::{math::Matrix2d, Context};
-use opengl_graphics::{GlGraphics, GlyphCache};
+use graphics::character::CharacterCache;
+use graphics::{math::Matrix2d, Context, DrawState, Image, Line, Rectangle, Text};
+use opengl_graphics::{Filter, GlGraphics, GlyphCache, Texture, TextureSettings};
+
+/// Index of a font added to `SharpGraphics`, the default is the font it was created with
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FontId(usize);
 
 pub struct SharpGraphics {
     gl: GlGraphics,
-    glyphs: GlyphCache<'static>,
+    fonts: Vec<GlyphCache<'static>>,
+    /// one texture per frame slot, reused while the frame size stays the same
+    frame_textures: Vec<Option<Texture>>,
+    /// carries the clip rectangle set by `set_clip`
+    draw_state: DrawState,
+    /// framebuffer pixels per window point, used to place the clip rectangle
+    pixel_scale: f64,
 }
 
 impl SharpGraphics {
-    pub fn new<'a>(gl: GlGraphics, glyphs: GlyphCache<'static>) -> SharpGraphics {
+    pub fn new(gl: GlGraphics, glyphs: GlyphCache<'static>) -> SharpGraphics {
         SharpGraphics {
-            gl: gl,
-            glyphs: glyphs,
+            gl,
+            fonts: vec![glyphs],
+            frame_textures: Vec::new(),
+            draw_state: DrawState::default(),
+            pixel_scale: 1.0,
         }
     }
 
@@ -20,30 +35,74 @@ impl SharpGraphics {
     }
     /// [x1, y1, x2, y2]
     pub fn draw_line(&mut self, color: [f32; 4], line: [f64; 4], transform: Matrix2d) {
-        graphics::line(color, 1.0, line, transform, &mut self.gl);
+        Line::new(color, 1.0).draw(line, &self.draw_state, transform, &mut self.gl);
     }
 
     /// Rectangle dimensions: [x, y, w, h]
     pub fn draw_rectangle(&mut self, color: [f32; 4], rect: [f64; 4], transform: Matrix2d) {
-        graphics::rectangle(color, rect, transform, &mut self.gl);
+        Rectangle::new(color).draw(rect, &self.draw_state, transform, &mut self.gl);
+    }
+
+    pub fn add_font(&mut self, glyphs: GlyphCache<'static>) -> FontId {
+        self.fonts.push(glyphs);
+        FontId(self.fonts.len() - 1)
+    }
+
+    /// Width of `text` in window points, from the glyph advances of `font`
+    pub fn text_width(&mut self, font: FontId, font_size: u32, text: &str) -> f64 {
+        self.fonts[font.0].width(font_size, text).unwrap_or(0.0)
     }
 
     pub fn draw_text(
         &mut self,
         color: [f32; 4],
+        font: FontId,
         font_size: u32,
         text_slice: &str,
         location: Matrix2d,
     ) {
-        graphics::text(
-            color,
-            font_size,
-            text_slice,
-            &mut self.glyphs,
-            location,
-            &mut self.gl,
-        )
-        .expect("write text failure");
+        Text::new_color(color, font_size)
+            .draw(
+                text_slice,
+                &mut self.fonts[font.0],
+                &self.draw_state,
+                location,
+                &mut self.gl,
+            )
+            .expect("write text failure");
+    }
+
+    /// Uploads `frame` into the texture for `slot` and draws it at `transform`.
+    /// Every frame drawn between `draw_begin` and `draw_end` needs its own slot.
+    pub fn draw_frame(&mut self, slot: usize, frame: &Frame, transform: Matrix2d) {
+        use graphics::ImageSize;
+
+        if self.frame_textures.len() <= slot {
+            self.frame_textures.resize_with(slot + 1, || None);
+        }
+        let image = frame.image();
+        let texture = match self.frame_textures[slot].take() {
+            Some(mut texture) if texture.get_size() == image.dimensions() => {
+                texture.update(image);
+                texture
+            }
+            _ => Texture::from_image(image, &TextureSettings::new().filter(Filter::Nearest)),
+        };
+        Image::new().draw(&texture, &self.draw_state, transform, &mut self.gl);
+        self.frame_textures[slot] = Some(texture);
+    }
+
+    /// Limits drawing to `[x, y, w, h]` in window points, or lifts the limit on `None`
+    pub fn set_clip(&mut self, clip: Option<[f64; 4]>) {
+        self.draw_state.scissor = clip.map(|[x, y, w, h]| {
+            let scale = self.pixel_scale;
+            [
+                (x * scale).max(0.0) as u32,
+                (y * scale).max(0.0) as u32,
+                (w * scale).max(0.0) as u32,
+                (h * scale).max(0.0) as u32,
+            ]
+        });
     }
 
     pub fn draw<F, U>(&mut self, _viewport: graphics::Viewport, _f: F) -> U
@@ -57,6 +116,8 @@ impl SharpGraphics {
     }
 
     pub fn draw_begin(&mut self, viewport: graphics::Viewport) -> Context {
+        self.pixel_scale = f64::from(viewport.draw_size[0]) / viewport.window_size[0].max(1.0);
+        self.draw_state = DrawState::default();
         self.gl.draw_begin(viewport)
     }
 
@@ -0,0 +1,181 @@
+//! What can be seen from where, for anything that needs to know without drawing
+//!
+//! Every query casts `Ray`s, so it sees exactly what the 3d view would: glass,
+//! railings and other tiles that don't block sight are looked through, and
+//! rays stop at the first tile that does.
+
+use board::Board;
+use point::BoardPoint;
+use ray::Ray;
+use std::collections::BTreeSet;
+use std::f64::consts::PI;
+
+/// Pixels a target can sit behind a wall face and still count as on it
+const FACE_EPSILON: f64 = 1e-6;
+
+/// Whether a straight line from `from` to `to` reaches `to` before any tile
+/// that blocks sight, a point on a wall face counts as seen
+pub fn can_see(board: &Board, from: BoardPoint, to: BoardPoint) -> bool {
+    let (dx, dy) = (to.x - from.x, to.y - from.y);
+    let mut ray = Ray::new();
+    ray.update(from, dy.atan2(dx), board);
+    match ray.wall_intersection {
+        Some(_) => dx.hypot(dy) * board.block_size <= ray.wall_distance + FACE_EPSILON,
+        None => true,
+    }
+}
+
+/// Board indices of every tile `rays` rays spread across `fov` radians around
+/// `angle` pass through from `origin`, walls they stop at included
+///
+/// Tiles far away can fall between two rays, more rays miss fewer of them.
+pub fn visible_tiles(
+    board: &Board,
+    origin: BoardPoint,
+    angle: f64,
+    fov: f64,
+    rays: usize,
+) -> BTreeSet<usize> {
+    let pixel_origin = BoardPoint {
+        x: origin.x * board.block_size,
+        y: origin.y * board.block_size,
+    };
+    let mut tiles = BTreeSet::new();
+    tiles.extend(board.get_index(origin));
+    for ray in cast(board, origin, angle, fov, rays) {
+        let reach = match ray.wall_intersection {
+            Some(_) => ray.wall_distance + FACE_EPSILON,
+            None => f64::INFINITY,
+        };
+        tiles.extend(
+            ray.x_intercepts
+                .iter()
+                .chain(ray.y_intercepts.iter())
+                .filter(|point| point.get_distance(pixel_origin) <= reach)
+                .filter_map(|point| point.board_index),
+        );
+    }
+    tiles
+}
+
+/// Outline of what `rays` rays spread across `fov` radians around `angle` see
+/// from `origin`, in board units
+///
+/// The points are where each ray stops, in order, starting with `origin` unless
+/// the rays go all the way round. Rays that hit nothing stop at the board edge.
+pub fn visibility_polygon(
+    board: &Board,
+    origin: BoardPoint,
+    angle: f64,
+    fov: f64,
+    rays: usize,
+) -> Vec<BoardPoint> {
+    let mut polygon = Vec::with_capacity(rays + 1);
+    if fov < PI * 2.0 {
+        polygon.push(origin);
+    }
+    for ray in cast(board, origin, angle, fov, rays) {
+        polygon.push(match ray.wall_intersection {
+            Some(point) => BoardPoint {
+                x: point.x / board.block_size,
+                y: point.y / board.block_size,
+            },
+            None => edge_point(board, origin, ray.angle),
+        });
+    }
+    polygon
+}
+
+/// `count` rays spread evenly across `fov`, the way players cast theirs
+///
+/// Across a full circle the last ray would land on the first, so the rays are
+/// spaced as if there were one more.
+fn cast(board: &Board, origin: BoardPoint, angle: f64, fov: f64, count: usize) -> Vec<Ray> {
+    let (first, tick) = if count > 1 && fov >= PI * 2.0 {
+        (angle - fov / 2.0, fov / count as f64)
+    } else if count > 1 {
+        (angle - fov / 2.0, fov / (count - 1) as f64)
+    } else {
+        (angle, 0.0)
+    };
+    (0..count)
+        .map(|i| {
+            let mut ray = Ray::new();
+            ray.update(origin, first + tick * i as f64, board);
+            ray
+        })
+        .collect()
+}
+
+/// Where a line from `origin` at `angle` leaves the board
+fn edge_point(board: &Board, origin: BoardPoint, angle: f64) -> BoardPoint {
+    let (sin, cos) = angle.sin_cos();
+    let exit = |start: f64, delta: f64, size: usize| {
+        if delta > 0.0 {
+            (size as f64 - start) / delta
+        } else if delta < 0.0 {
+            -start / delta
+        } else {
+            f64::INFINITY
+        }
+    };
+    let length = exit(origin.x, cos, board.tiles_x)
+        .min(exit(origin.y, sin, board.tiles_y))
+        .max(0.0);
+    BoardPoint {
+        x: origin.x + cos * length,
+        y: origin.y + sin * length,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_board::board_from;
+
+    fn point(x: f64, y: f64) -> BoardPoint {
+        BoardPoint { x, y }
+    }
+
+    #[test]
+    fn walls_between_two_points_hide_them() {
+        let board = board_from(&["#######", "#.....#", "#..#..#", "#.....#", "#######"]);
+        assert!(!can_see(&board, point(1.5, 2.5), point(5.5, 2.5)));
+        assert!(!can_see(&board, point(5.5, 2.5), point(1.5, 2.5)));
+        assert!(!can_see(&board, point(2.2, 2.9), point(4.8, 2.1)));
+        assert!(can_see(&board, point(1.5, 1.5), point(5.5, 1.5)));
+        assert!(can_see(&board, point(1.5, 3.5), point(5.5, 3.1)));
+    }
+
+    #[test]
+    fn walls_in_the_bottom_row_hide_what_is_behind_them() {
+        // rays along the last open row used to miss walls at the board's bottom edge
+        let board = board_from(&["#######", "#.....#", "#..#..#"]);
+        assert!(!can_see(&board, point(1.2, 2.9), point(5.8, 2.1)));
+        assert!(!can_see(&board, point(5.9, 2.99), point(1.1, 2.99)));
+    }
+
+    #[test]
+    fn glass_is_seen_through() {
+        let board = board_from(&["#######", "#..o..#", "#######"]);
+        assert!(can_see(&board, point(1.5, 1.5), point(5.5, 1.5)));
+    }
+
+    #[test]
+    fn full_circle_casts_every_ray_at_its_own_angle() {
+        let board = board_from(&["#####", "#...#", "#...#", "#...#", "#####"]);
+        let rays = cast(&board, point(2.5, 2.5), 0.0, PI * 2.0, 8);
+        let first = rays[0].angle;
+        for (i, ray) in rays.iter().enumerate() {
+            assert!((ray.angle - first - PI / 4.0 * i as f64).abs() < 1e-9);
+        }
+
+        let polygon = visibility_polygon(&board, point(2.5, 2.5), 0.0, PI * 2.0, 8);
+        assert_eq!(polygon.len(), 8);
+        for (i, a) in polygon.iter().enumerate() {
+            for b in polygon.iter().skip(i + 1) {
+                assert!((a.x - b.x).hypot(a.y - b.y) > 1e-6);
+            }
+        }
+    }
+}
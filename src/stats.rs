@@ -0,0 +1,166 @@
+use colors;
+use graphics::math::Matrix2d;
+use layout::Rect;
+use ray::Ray;
+use sharp_graphics::SharpGraphics;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Frames kept for the graph and the percentiles
+const HISTORY: usize = 240;
+/// Frame time the graph is scaled to, twice a 60 Hz frame
+const GRAPH_MS: f64 = 1000.0 / 30.0;
+
+/// What one rendered frame cost, including every update since the previous frame
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FrameStats {
+    pub rays_cast: usize,
+    /// grid crossings tested by all rays
+    pub cells_visited: usize,
+    pub ray_casting: Duration,
+    pub draw_2d: Duration,
+    pub draw_3d: Duration,
+    pub draw_mini_map: Duration,
+    /// time since the previous frame finished
+    pub frame_time: Duration,
+}
+
+/// Rolling per-frame statistics
+pub struct Stats {
+    current: FrameStats,
+    history: VecDeque<FrameStats>,
+    last_frame: Option<Instant>,
+}
+
+impl Default for Stats {
+    fn default() -> Stats {
+        Stats {
+            current: FrameStats::default(),
+            history: VecDeque::with_capacity(HISTORY),
+            last_frame: None,
+        }
+    }
+}
+
+impl Stats {
+    /// Counts `rays` cast in `elapsed` towards the current frame
+    pub fn record_rays<'a, I: Iterator<Item = &'a Ray>>(&mut self, rays: I, elapsed: Duration) {
+        for ray in rays {
+            self.current.rays_cast += 1;
+            self.current.cells_visited += ray.x_intercepts.len() + ray.y_intercepts.len();
+        }
+        self.current.ray_casting += elapsed;
+    }
+
+    pub fn record_2d(&mut self, elapsed: Duration) {
+        self.current.draw_2d += elapsed;
+    }
+
+    pub fn record_3d(&mut self, elapsed: Duration) {
+        self.current.draw_3d += elapsed;
+    }
+
+    pub fn record_mini_map(&mut self, elapsed: Duration) {
+        self.current.draw_mini_map += elapsed;
+    }
+
+    /// Closes the current frame and starts collecting the next one
+    pub fn finish_frame(&mut self) {
+        let now = Instant::now();
+        if let Some(last_frame) = self.last_frame {
+            self.current.frame_time = now - last_frame;
+        }
+        self.last_frame = Some(now);
+        if self.history.len() == HISTORY {
+            self.history.pop_front();
+        }
+        self.history.push_back(std::mem::take(&mut self.current));
+    }
+
+    /// Most recent finished frame
+    pub fn latest(&self) -> Option<&FrameStats> {
+        self.history.back()
+    }
+
+    /// Finished frames, oldest first
+    pub fn history(&self) -> impl Iterator<Item = &FrameStats> {
+        self.history.iter()
+    }
+
+    /// Frame time in milliseconds that `percent` of the kept frames came in under
+    pub fn frame_time_percentile(&self, percent: f64) -> f64 {
+        let mut times: Vec<f64> = self
+            .history()
+            .map(|frame| millis(frame.frame_time))
+            .collect();
+        if times.is_empty() {
+            return 0.0;
+        }
+        times.sort_by(|a, b| a.partial_cmp(b).expect("frame time is a number"));
+        let rank = (percent.clamp(0.0, 100.0) / 100.0 * (times.len() - 1) as f64).round();
+        times[rank as usize]
+    }
+
+    /// One bar per kept frame inside `rect`, stacked from the bottom as ray
+    /// casting, 3d, 2d and mini map time, with the whole frame time behind them
+    pub fn draw_graph(&self, transform: Matrix2d, graphics: &mut SharpGraphics, rect: Rect) {
+        graphics.draw_rectangle(colors::HUD_PANEL, rect.to_array(), transform);
+        let bar_width = rect.width / HISTORY as f64;
+        let scale = rect.height / GRAPH_MS;
+        let bottom = rect.y + rect.height;
+        let first = HISTORY - self.history.len();
+        for (i, frame) in self.history().enumerate() {
+            let x = rect.x + (first + i) as f64 * bar_width;
+            let frame_height = (millis(frame.frame_time) * scale).min(rect.height);
+            graphics.draw_rectangle(
+                colors::GRAPH_FRAME,
+                [x, bottom - frame_height, bar_width, frame_height],
+                transform,
+            );
+            let mut top = bottom;
+            for &(time, color) in [
+                (frame.ray_casting, colors::ORANGE_ALPHA),
+                (frame.draw_3d, colors::RED_ALPHA),
+                (frame.draw_2d, colors::BLUE_ALPHA),
+                (frame.draw_mini_map, colors::GREEN_ALPHA),
+            ]
+            .iter()
+            {
+                let height = (millis(time) * scale).min(top - rect.y);
+                top -= height;
+                graphics.draw_rectangle(color, [x, top, bar_width, height], transform);
+            }
+        }
+        // 60 Hz budget
+        let budget_y = bottom - 1000.0 / 60.0 * scale;
+        graphics.draw_line(
+            colors::BLACK,
+            [rect.x, budget_y, rect.x + rect.width, budget_y],
+            transform,
+        );
+    }
+}
+
+impl std::fmt::Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let latest = self.latest().cloned().unwrap_or_default();
+        write!(
+            f,
+            "Stats {{ rays: {}, cells: {}, cast: {:.2}ms, 3d: {:.2}ms, 2d: {:.2}ms, \
+             mini map: {:.2}ms, frame p50/p95/p99: {:.1}/{:.1}/{:.1}ms }}",
+            latest.rays_cast,
+            latest.cells_visited,
+            millis(latest.ray_casting),
+            millis(latest.draw_3d),
+            millis(latest.draw_2d),
+            millis(latest.draw_mini_map),
+            self.frame_time_percentile(50.0),
+            self.frame_time_percentile(95.0),
+            self.frame_time_percentile(99.0),
+        )
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
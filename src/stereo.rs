@@ -0,0 +1,81 @@
+use board::Board;
+use frame::Frame;
+use player::Player;
+use point::BoardPoint;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StereoMode {
+    Off,
+    /// left eye in the left half of the view, right eye in the right half
+    SideBySide,
+    /// left eye in the red channel, right eye in green and blue
+    Anaglyph,
+}
+
+impl StereoMode {
+    pub fn parse(value: &str) -> Option<StereoMode> {
+        match value {
+            "off" => Some(StereoMode::Off),
+            "side" | "side-by-side" => Some(StereoMode::SideBySide),
+            "anaglyph" => Some(StereoMode::Anaglyph),
+            _ => None,
+        }
+    }
+
+    pub fn next(self) -> StereoMode {
+        match self {
+            StereoMode::Off => StereoMode::SideBySide,
+            StereoMode::SideBySide => StereoMode::Anaglyph,
+            StereoMode::Anaglyph => StereoMode::Off,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StereoConfig {
+    pub mode: StereoMode,
+    /// distance between the eyes, in tiles
+    pub eye_distance: f64,
+}
+
+impl Default for StereoConfig {
+    fn default() -> StereoConfig {
+        StereoConfig {
+            mode: StereoMode::Off,
+            eye_distance: 0.1,
+        }
+    }
+}
+
+/// Left and right eye positions, half the eye distance either side of the player.
+/// An eye that would end up inside a wall stays at the player position.
+pub fn eye_positions(player: &Player, eye_distance: f64, board: &Board) -> [BoardPoint; 2] {
+    let offset = eye_distance / 2.0;
+    let eye = |angle: f64| {
+        let (sin, cos) = angle.sin_cos();
+        let point = BoardPoint {
+            x: player.position.x + cos * offset,
+            y: player.position.y + sin * offset,
+        };
+        if board.is_wall_at(point) {
+            player.position
+        } else {
+            point
+        }
+    };
+    [
+        eye(player.angle - std::f64::consts::FRAC_PI_2),
+        eye(player.angle + std::f64::consts::FRAC_PI_2),
+    ]
+}
+
+/// Keeps red from `left` and takes green and blue from `right`
+pub fn combine_anaglyph(left: &mut Frame, right: &Frame) {
+    for y in 0..left.height().min(right.height()) {
+        for x in 0..left.width().min(right.width()) {
+            let red = left.get(x, y)[0];
+            let [_, green, blue, _] = right.get(x, y);
+            left.set(x, y, [red, green, blue, 255]);
+        }
+    }
+}
@@ -0,0 +1,36 @@
+//! Small boards drawn as text for the module tests
+
+use board::Board;
+use std::path::Path;
+use tiles::TileRegistry;
+
+/// see-through tile that doesn't block movement
+pub const GLASS: u32 = 8;
+/// walkable tile costing 3 to cross
+pub const GRASS: u32 = 11;
+
+/// `#` is a wall, `o` glass, `g` tall grass and anything else open floor,
+/// one row per string
+pub fn board_from(rows: &[&str]) -> Board {
+    let tiles = rows
+        .iter()
+        .flat_map(|row| row.chars())
+        .map(|c| match c {
+            '#' => 1,
+            'o' => GLASS,
+            'g' => GRASS,
+            _ => 0,
+        })
+        .collect();
+    let mut board = Board::new(tiles, rows[0].len(), rows.len(), 50.0);
+    board.registry = TileRegistry::parse(
+        "(tiles: [\
+         (id: 8, name: \"glass\", map_color: (170, 220, 255, 255), \
+         wall: Color((170, 220, 255, 70)), opaque: false), \
+         (id: 11, name: \"tall grass\", map_color: (70, 150, 50, 255), \
+         wall: Color((70, 150, 50, 160)), solid: false, cost: 3.0)])",
+        Path::new(""),
+    )
+    .unwrap();
+    board
+}
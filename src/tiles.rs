@@ -0,0 +1,208 @@
+//! What every tile id is, read from a RON file so new kinds need no code
+//!
+//! ```text
+//! (
+//!     tiles: [
+//!         (id: 1, name: "red wall", map_color: (255, 0, 0, 230), wall: Color((255, 0, 0, 230))),
+//!         (id: 8, name: "glass", map_color: (160, 210, 255, 255),
+//!          wall: Color((160, 210, 255, 80)), opaque: false),
+//!         (id: 9, name: "crate", map_color: (150, 110, 60, 255),
+//!          wall: Texture("textures/crate.png"), height: 0.5),
+//!         (id: 10, name: "mud", map_color: (90, 60, 30, 255),
+//!          wall: Color((90, 60, 30, 255)), solid: false, height: 0.1, cost: 4.0),
+//!         (id: 11, name: "beacon", map_color: (255, 230, 50, 255),
+//!          wall: Animation(fps: 2.0, frames: [Color((255, 230, 50, 255)), Color((90, 75, 15, 255))])),
+//!         (id: 12, name: "open sky", map_color: (130, 185, 235, 255),
+//!          wall: Texture("textures/sky.png"), sky: true),
+//!     ],
+//! )
+//! ```
+//!
+//! Tiles are solid, opaque, one wall high, cost 1 to walk through and aren't sky
+//! unless they say otherwise. A sky texture wraps once around the view, from
+//! the top of it down to the horizon. Id 0 is always empty floor. A map's own palette is drawn
+//! over these looks.
+
+use animation::{TileAnimation, TileFrame};
+use map::document::{from_rgba, Rgba};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// How a tile's walls look in the 3d view
+#[derive(Debug, Clone, Deserialize)]
+pub enum WallLook {
+    Color(Rgba),
+    /// image file, relative to the registry file
+    Texture(String),
+    /// colours and images shown one after another, `fps` times a second
+    Animation {
+        fps: f64,
+        frames: Vec<WallLook>,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TileKind {
+    pub id: u32,
+    pub name: String,
+    /// colour on the 2d map and the mini map
+    pub map_color: Rgba,
+    pub wall: WallLook,
+    /// players can't walk into it
+    #[serde(default = "default_true")]
+    pub solid: bool,
+    /// rays stop at it, otherwise whatever is behind shows through
+    #[serde(default = "default_true")]
+    pub opaque: bool,
+    /// wall height as a fraction of a full wall, standing on the floor
+    #[serde(default = "default_height")]
+    pub height: f64,
+    /// what walking into it costs a route when it isn't solid, empty floor costs 1
+    #[serde(default = "default_cost")]
+    pub cost: f64,
+    /// on the ceiling layer, the ceiling is open and its look is the sky behind
+    /// it, which never gets darker with distance
+    #[serde(default)]
+    pub sky: bool,
+}
+
+#[derive(Deserialize)]
+struct RegistryFile {
+    tiles: Vec<TileKind>,
+}
+
+/// Tile kinds by id, with their wall textures and animations loaded
+#[derive(Default)]
+pub struct TileRegistry {
+    kinds: HashMap<u32, TileKind>,
+    textures: HashMap<u32, TileFrame>,
+    animations: HashMap<u32, TileAnimation>,
+}
+
+impl TileRegistry {
+    pub fn load(path: &str) -> Result<TileRegistry, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("could not read '{}': {}", path, e))?;
+        let base_dir = Path::new(path).parent().unwrap_or_else(|| Path::new(""));
+        TileRegistry::parse(&text, base_dir).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    /// Reads a registry, loading textures relative to `base_dir`
+    pub fn parse(text: &str, base_dir: &Path) -> Result<TileRegistry, String> {
+        let file: RegistryFile = ron::from_str(text).map_err(|e| e.to_string())?;
+        let mut registry = TileRegistry::default();
+        for kind in file.tiles {
+            if kind.id == 0 {
+                return Err(format!("'{}' uses id 0, which is empty floor", kind.name));
+            }
+            if !(kind.height > 0.0 && kind.height <= 1.0) {
+                return Err(format!(
+                    "'{}' has height {}, expected more than 0 and at most 1",
+                    kind.name, kind.height
+                ));
+            }
+            if kind.cost.is_nan() || kind.cost < 1.0 {
+                return Err(format!(
+                    "'{}' has cost {}, expected at least 1",
+                    kind.name, kind.cost
+                ));
+            }
+            match kind.wall {
+                WallLook::Color(_) => {}
+                WallLook::Texture(ref texture) => {
+                    let frame = load_texture(base_dir, texture)?;
+                    registry.textures.insert(kind.id, frame);
+                }
+                WallLook::Animation { fps, ref frames } => {
+                    if frames.is_empty() || !(fps.is_finite() && fps >= 0.0) {
+                        return Err(format!(
+                            "'{}' needs at least one animation frame and fps of 0 or more",
+                            kind.name
+                        ));
+                    }
+                    let frames = frames
+                        .iter()
+                        .map(|frame| match frame {
+                            WallLook::Color(color) => Ok(TileFrame::Color(from_rgba(*color))),
+                            WallLook::Texture(texture) => load_texture(base_dir, texture),
+                            WallLook::Animation { .. } => Err(format!(
+                                "'{}' has an animation inside an animation",
+                                kind.name
+                            )),
+                        })
+                        .collect::<Result<Vec<TileFrame>, String>>()?;
+                    registry
+                        .animations
+                        .insert(kind.id, TileAnimation { frames, fps });
+                }
+            }
+            if let Some(previous) = registry.kinds.insert(kind.id, kind) {
+                return Err(format!("tile id {} is defined twice", previous.id));
+            }
+        }
+        Ok(registry)
+    }
+
+    /// Every id with a kind, in no particular order
+    pub fn ids(&self) -> impl Iterator<Item = u32> + '_ {
+        self.kinds.keys().cloned()
+    }
+
+    pub fn get(&self, tile: u32) -> Option<&TileKind> {
+        self.kinds.get(&tile)
+    }
+
+    pub fn map_color(&self, tile: u32) -> Option<[f32; 4]> {
+        self.get(tile).map(|kind| from_rgba(kind.map_color))
+    }
+
+    /// Flat wall colour, the average of the texture for textured tiles and of
+    /// the first frame for animated ones
+    pub fn wall_color(&self, tile: u32) -> Option<[f32; 4]> {
+        match self.get(tile)?.wall {
+            WallLook::Color(color) => Some(from_rgba(color)),
+            WallLook::Texture(_) => self.texture(tile).map(TileFrame::average),
+            WallLook::Animation { .. } => self
+                .animation(tile)
+                .map(|animation| animation.frame_at(0.0).average()),
+        }
+    }
+
+    pub fn texture(&self, tile: u32) -> Option<&TileFrame> {
+        self.textures.get(&tile)
+    }
+
+    pub fn animation(&self, tile: u32) -> Option<&TileAnimation> {
+        self.animations.get(&tile)
+    }
+
+    /// Every loaded texture and animation frame
+    pub fn frames_mut(&mut self) -> impl Iterator<Item = &mut TileFrame> {
+        self.textures.values_mut().chain(
+            self.animations
+                .values_mut()
+                .flat_map(|animation| animation.frames.iter_mut()),
+        )
+    }
+}
+
+fn load_texture(base_dir: &Path, texture: &str) -> Result<TileFrame, String> {
+    let path = base_dir.join(texture);
+    let image = image::open(&path)
+        .map_err(|e| format!("could not load texture '{}': {}", path.display(), e))?
+        .to_rgba();
+    Ok(TileFrame::texture(image))
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_height() -> f64 {
+    1.0
+}
+
+fn default_cost() -> f64 {
+    1.0
+}